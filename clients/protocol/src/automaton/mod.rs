@@ -0,0 +1,279 @@
+// The client side of the protocol, as drawn in clients/client-automaton.dot:
+//
+//     start -connect-> S1 -CONNECTED-> S2 -JOIN-> S3 -START-> turn
+//     turn -EVENTS-> wait -ACTIONS-> turn
+//     turn -END-> end
+//
+// The automaton doesn't know how messages look on the wire, each client parses them into
+// its own types and tells the automaton which kind of message it got. Replies (join and
+// actions) come from the hooks and are sent right away, so S2 and wait are only passed
+// through while a message is handled.
+//
+// If the connection drops mid-game the client connects and joins again under the same team
+// name. server/server then hands the team to the new connection, but it can't send start to
+// a game that's already running, so the first events take us straight back to turn and the
+// hooks rebuild their state from them. Any other join to a running game is answered with an
+// error message ("Already started") and nothing more, the clients give up the game then.
+//
+// Both serenity and clients/rust play through this.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum State {
+    // Not connected yet
+    Start,
+    // S1, connected and waiting for the server to say hello
+    Connecting,
+    // S2, we should join
+    Connected,
+    // S3, joined and waiting for the game to start
+    Joined,
+    // Waiting for the events of the next round
+    Turn,
+    // We should answer the round with our actions
+    Wait,
+    End,
+}
+
+// What a client does at each step of the game
+pub trait Hooks {
+    type Connected;
+    type Start;
+    type Events;
+    type End;
+    // The message sent back to the server, e.g. the JSON of a join or actions message
+    type Reply;
+
+    // Returns the join message
+    fn on_connected(&mut self, msg: Self::Connected) -> Self::Reply;
+    fn on_start(&mut self, msg: Self::Start);
+    // We joined a running game again after a reconnect, called before on_round
+    fn on_resume(&mut self, _msg: &Self::Events) {}
+    // Returns the actions message for the round
+    fn on_round(&mut self, msg: Self::Events) -> Self::Reply;
+    fn on_end(&mut self, msg: Self::End);
+}
+
+pub enum Incoming<H: Hooks> {
+    Connected(H::Connected),
+    Start(H::Start),
+    Events(H::Events),
+    End(H::End),
+}
+
+impl<H: Hooks> Incoming<H> {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Incoming::Connected(_) => "connected",
+            Incoming::Start(_) => "start",
+            Incoming::Events(_) => "events",
+            Incoming::End(_) => "end",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Step<R> {
+    // Send this to the server
+    Reply(R),
+    // Nothing to send
+    Nothing,
+    // The message made no sense in this state (out of order or repeated) and was skipped
+    Ignored(String),
+    // The game is over
+    Finished,
+}
+
+pub struct Automaton<H: Hooks> {
+    state: State,
+    hooks: H,
+    // Reconnected during a game that hasn't given us events since
+    resuming: bool,
+}
+
+impl<H: Hooks> Automaton<H> {
+    pub fn new(hooks: H) -> Automaton<H> {
+        Automaton {
+            state: State::Start,
+            hooks: hooks,
+            resuming: false,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn state(&self) -> State {
+        self.state
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.state == State::End
+    }
+
+    // Joined again after losing the connection mid-game, and no events since
+    pub fn is_resuming(&self) -> bool {
+        self.resuming
+    }
+
+    pub fn hooks(&self) -> &H {
+        &self.hooks
+    }
+
+    #[allow(dead_code)]
+    pub fn hooks_mut(&mut self) -> &mut H {
+        &mut self.hooks
+    }
+
+    // We have a connection, the server should greet us next
+    pub fn connect(&mut self) {
+        self.state = State::Connecting;
+    }
+
+    // We have a new connection after losing the old one
+    pub fn reconnect(&mut self) {
+        self.resuming = self.resuming || self.state == State::Turn || self.state == State::Wait;
+        self.state = State::Connecting;
+    }
+
+    pub fn handle(&mut self, msg: Incoming<H>) -> Step<H::Reply> {
+        if self.resuming && self.state == State::Joined {
+            if let Incoming::Events(ref msg) = msg {
+                self.hooks.on_resume(msg);
+                self.resuming = false;
+                self.state = State::Turn;
+            }
+        }
+
+        match (self.state, msg) {
+            (State::Connecting, Incoming::Connected(msg)) => {
+                self.state = State::Connected;
+                let join = self.hooks.on_connected(msg);
+                self.state = State::Joined;
+                Step::Reply(join)
+            }
+            (State::Joined, Incoming::Start(msg)) => {
+                self.resuming = false;
+                self.hooks.on_start(msg);
+                self.state = State::Turn;
+                Step::Nothing
+            }
+            (State::Turn, Incoming::Events(msg)) => {
+                self.state = State::Wait;
+                let actions = self.hooks.on_round(msg);
+                self.state = State::Turn;
+                Step::Reply(actions)
+            }
+            // The game can end before it started for us, e.g. if it was full
+            (State::Joined, Incoming::End(msg)) |
+            (State::Turn, Incoming::End(msg)) => {
+                self.hooks.on_end(msg);
+                self.state = State::End;
+                Step::Finished
+            }
+            (State::End, msg) => {
+                Step::Ignored(format!("Got {} after the game ended", msg.name()))
+            }
+            (state, msg) => {
+                Step::Ignored(format!("Got {} in state {:?}", msg.name(), state))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+struct Recorder {
+    calls: Vec<&'static str>,
+}
+
+#[cfg(test)]
+impl Hooks for Recorder {
+    type Connected = ();
+    type Start = ();
+    type Events = u32;
+    type End = ();
+    type Reply = String;
+
+    fn on_connected(&mut self, _msg: ()) -> String {
+        self.calls.push("connected");
+        "join".to_string()
+    }
+
+    fn on_start(&mut self, _msg: ()) {
+        self.calls.push("start");
+    }
+
+    fn on_resume(&mut self, _round_id: &u32) {
+        self.calls.push("resume");
+    }
+
+    fn on_round(&mut self, round_id: u32) -> String {
+        self.calls.push("round");
+        format!("actions {}", round_id)
+    }
+
+    fn on_end(&mut self, _msg: ()) {
+        self.calls.push("end");
+    }
+}
+
+#[test]
+fn automaton_follows_the_protocol() {
+    let mut automaton = Automaton::new(Recorder { calls: Vec::new() });
+    automaton.connect();
+
+    match automaton.handle(Incoming::Connected(())) {
+        Step::Reply(reply) => assert_eq!(reply, "join"),
+        step => panic!("Expected a join, got {:?}", step),
+    }
+    // Events before start can't be played, and a second start changes nothing
+    match automaton.handle(Incoming::Events(0)) {
+        Step::Ignored(_) => (),
+        step => panic!("Expected events before start to be ignored, got {:?}", step),
+    }
+    match automaton.handle(Incoming::Start(())) {
+        Step::Nothing => (),
+        step => panic!("Expected nothing to send on start, got {:?}", step),
+    }
+    match automaton.handle(Incoming::Start(())) {
+        Step::Ignored(_) => (),
+        step => panic!("Expected a repeated start to be ignored, got {:?}", step),
+    }
+    match automaton.handle(Incoming::Events(1)) {
+        Step::Reply(reply) => assert_eq!(reply, "actions 1"),
+        step => panic!("Expected actions, got {:?}", step),
+    }
+    match automaton.handle(Incoming::End(())) {
+        Step::Finished => (),
+        step => panic!("Expected the game to end, got {:?}", step),
+    }
+    match automaton.handle(Incoming::Events(2)) {
+        Step::Ignored(_) => (),
+        step => panic!("Expected events after end to be ignored, got {:?}", step),
+    }
+
+    assert!(automaton.is_finished());
+    assert_eq!(automaton.hooks().calls, vec!["connected", "start", "round", "end"]);
+}
+
+#[test]
+fn automaton_resumes_after_reconnect() {
+    let mut automaton = Automaton::new(Recorder { calls: Vec::new() });
+    automaton.connect();
+    automaton.handle(Incoming::Connected(()));
+    automaton.handle(Incoming::Start(()));
+    automaton.handle(Incoming::Events(1));
+
+    // The connection dropped, we connect and join again and the game goes on without a start
+    automaton.reconnect();
+    match automaton.handle(Incoming::Connected(())) {
+        Step::Reply(reply) => assert_eq!(reply, "join"),
+        step => panic!("Expected a join, got {:?}", step),
+    }
+    assert!(automaton.is_resuming());
+    match automaton.handle(Incoming::Events(3)) {
+        Step::Reply(reply) => assert_eq!(reply, "actions 3"),
+        step => panic!("Expected actions, got {:?}", step),
+    }
+
+    assert_eq!(automaton.state(), State::Turn);
+    assert!(!automaton.is_resuming());
+    assert_eq!(automaton.hooks().calls, vec!["connected", "start", "round", "connected", "resume", "round"]);
+}
@@ -0,0 +1,13 @@
+// The Tyckiting protocol for Rust clients: the messages of server/DETAILS.md, the hex
+// positions they talk about and the client's side of the game as an automaton. Also RandomAi,
+// so serenity's tournament plays against the same random player clients/rust plays with.
+
+extern crate serde;
+extern crate serde_json;
+extern crate rand;
+
+pub mod strings;
+pub mod position;
+pub mod messages;
+pub mod automaton;
+pub mod random;
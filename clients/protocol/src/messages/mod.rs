@@ -0,0 +1,409 @@
+// Every message of server/DETAILS.md, as sent on the wire. Messages from the server also
+// come with a "type", the ones without an event_type field just don't keep it.
+
+use std::fmt;
+use position::Pos;
+use serde::{de, ser, Deserialize, Deserializer, Serialize, Serializer};
+use strings::{CANNON, DAMAGED, DETECTED, DIE, HIT, MOVE, NOACTION, RADAR, RADARECHO, SEE, SEEASTEROID};
+#[cfg(test)]
+use {serde, serde_json};
+
+include!(concat!(env!("OUT_DIR"), "/messages.rs"));
+
+// The winnerTeamId of the end message when nobody won
+pub const TIE_TEAM_ID: i16 = -1;
+
+impl fmt::Display for Action {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {} {}", self.bot_id, self.kind, self.pos)
+    }
+}
+
+impl ActionKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            ActionKind::Move => MOVE,
+            ActionKind::Radar => RADAR,
+            ActionKind::Cannon => CANNON,
+            ActionKind::Noaction => NOACTION,
+        }
+    }
+}
+
+impl fmt::Display for ActionKind {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+// An event of the events message. The "event" field tells which one it is, and with that
+// which of the other fields it has to have.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Event {
+    Hit(HitEvent),
+    Die(DieEvent),
+    See(SeeEvent),
+    SeeAsteroid(SeeAsteroidEvent),
+    Echo(EchoEvent),
+    Detected(DetectedEvent),
+    Damaged(DamagedEvent),
+    Move(MoveEvent),
+    Noaction(NoactionEvent),
+}
+
+// Why an entry of the events list isn't an Event
+#[derive(Debug, Clone, PartialEq)]
+pub enum EventError {
+    UnknownEvent(String),
+    // e.g. a hit without a botId
+    MissingField { event: String, field: &'static str },
+}
+
+impl fmt::Display for EventError {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            EventError::UnknownEvent(ref event) => write!(f, "Unknown event type {:?}", event),
+            EventError::MissingField { ref event, field } => write!(f, "Event {} is missing {}", event, field),
+        }
+    }
+}
+
+// An entry of the events list, read on its own: a malformed event only spoils its own entry
+// and not the whole message, we'd rather act on the rest than miss the round.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MaybeEvent(pub Result<Event, EventError>);
+
+impl From<Event> for MaybeEvent {
+    fn from(event: Event) -> MaybeEvent {
+        MaybeEvent(Ok(event))
+    }
+}
+
+// Which kind of event an Event is, for picking events out of a list without matching
+// on what's inside them
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum EventKind {
+    Hit,
+    Die,
+    See,
+    SeeAsteroid,
+    Echo,
+    Detected,
+    Damaged,
+    Move,
+    Noaction,
+}
+
+impl EventKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            EventKind::Hit => HIT,
+            EventKind::Die => DIE,
+            EventKind::See => SEE,
+            EventKind::SeeAsteroid => SEEASTEROID,
+            EventKind::Echo => RADARECHO,
+            EventKind::Detected => DETECTED,
+            EventKind::Damaged => DAMAGED,
+            EventKind::Move => MOVE,
+            EventKind::Noaction => NOACTION,
+        }
+    }
+}
+
+impl Event {
+    pub fn kind(&self) -> EventKind {
+        match *self {
+            Event::Hit(_) => EventKind::Hit,
+            Event::Die(_) => EventKind::Die,
+            Event::See(_) => EventKind::See,
+            Event::SeeAsteroid(_) => EventKind::SeeAsteroid,
+            Event::Echo(_) => EventKind::Echo,
+            Event::Detected(_) => EventKind::Detected,
+            Event::Damaged(_) => EventKind::Damaged,
+            Event::Move(_) => EventKind::Move,
+            Event::Noaction(_) => EventKind::Noaction,
+        }
+    }
+
+    // The bot the event is about, None for the ones about a hex
+    pub fn bot_id(&self) -> Option<i16> {
+        match *self {
+            Event::Hit(ev) => Some(ev.bot_id),
+            Event::Die(ev) => Some(ev.bot_id),
+            Event::See(ev) => Some(ev.bot_id),
+            Event::Detected(ev) => Some(ev.bot_id),
+            Event::Damaged(ev) => Some(ev.bot_id),
+            Event::Move(ev) => Some(ev.bot_id),
+            Event::Noaction(ev) => Some(ev.bot_id),
+            Event::SeeAsteroid(_) | Event::Echo(_) => None,
+        }
+    }
+
+    pub fn pos(&self) -> Option<Pos> {
+        match *self {
+            Event::See(ev) => Some(ev.pos),
+            Event::SeeAsteroid(ev) => Some(ev.pos),
+            Event::Echo(ev) => Some(ev.pos),
+            Event::Move(ev) => Some(ev.pos),
+            _ => None,
+        }
+    }
+
+    fn source(&self) -> Option<i16> {
+        match *self {
+            Event::Hit(ev) => Some(ev.source),
+            Event::See(ev) => Some(ev.source),
+            _ => None,
+        }
+    }
+
+    fn damage(&self) -> Option<i16> {
+        match *self {
+            Event::Damaged(ev) => Some(ev.damage),
+            _ => None,
+        }
+    }
+}
+
+// Every field any event has, as read from the wire
+#[derive(Default)]
+struct EventFields {
+    event: Option<String>,
+    bot_id: Option<i16>,
+    source: Option<i16>,
+    pos: Option<Pos>,
+    damage: Option<i16>,
+}
+
+impl EventFields {
+    // A field the event's type needs, or an error naming it
+    fn require<T: Copy>(&self, value: Option<T>, field: &'static str) -> Result<T, EventError> {
+        match value {
+            Some(value) => Ok(value),
+            None => Err(EventError::MissingField { event: self.event.clone().unwrap_or(String::new()), field: field }),
+        }
+    }
+
+    fn to_event(&self) -> Result<Event, EventError> {
+        let event = match self.event.as_ref().map(|name| name.as_ref()) {
+            Some(HIT) => Event::Hit(HitEvent {
+                bot_id: try!(self.require(self.bot_id, "botId")),
+                source: try!(self.require(self.source, "source")),
+            }),
+            Some(DIE) => Event::Die(DieEvent {
+                bot_id: try!(self.require(self.bot_id, "botId")),
+            }),
+            Some(SEE) => Event::See(SeeEvent {
+                source: try!(self.require(self.source, "source")),
+                bot_id: try!(self.require(self.bot_id, "botId")),
+                pos: try!(self.require(self.pos, "pos")),
+            }),
+            Some(SEEASTEROID) => Event::SeeAsteroid(SeeAsteroidEvent {
+                pos: try!(self.require(self.pos, "pos")),
+            }),
+            Some(RADARECHO) => Event::Echo(EchoEvent {
+                pos: try!(self.require(self.pos, "pos")),
+            }),
+            Some(DETECTED) => Event::Detected(DetectedEvent {
+                bot_id: try!(self.require(self.bot_id, "botId")),
+            }),
+            Some(DAMAGED) => Event::Damaged(DamagedEvent {
+                bot_id: try!(self.require(self.bot_id, "botId")),
+                damage: try!(self.require(self.damage, "damage")),
+            }),
+            Some(MOVE) => Event::Move(MoveEvent {
+                bot_id: try!(self.require(self.bot_id, "botId")),
+                pos: try!(self.require(self.pos, "pos")),
+            }),
+            Some(NOACTION) => Event::Noaction(NoactionEvent {
+                bot_id: try!(self.require(self.bot_id, "botId")),
+            }),
+            _ => return Err(EventError::UnknownEvent(self.event.clone().unwrap_or(String::new()))),
+        };
+        Ok(event)
+    }
+}
+
+// Reads the fields of one event, whatever its type, and only then looks at which it is
+struct EventVisitor;
+
+impl de::Visitor for EventVisitor {
+    type Value = MaybeEvent;
+
+    fn visit_map<V: de::MapVisitor>(&mut self, mut visitor: V) -> Result<MaybeEvent, V::Error> {
+        let mut fields = EventFields::default();
+        while let Some(key) = try!(visitor.visit_key::<String>()) {
+            match key.as_ref() {
+                "event" => fields.event = Some(try!(visitor.visit_value())),
+                "botId" => fields.bot_id = Some(try!(visitor.visit_value())),
+                "source" => fields.source = Some(try!(visitor.visit_value())),
+                "pos" => fields.pos = Some(try!(visitor.visit_value())),
+                "damage" => fields.damage = Some(try!(visitor.visit_value())),
+                _ => { try!(visitor.visit_value::<de::impls::IgnoredAny>()); }
+            }
+        }
+        try!(visitor.end());
+        Ok(MaybeEvent(fields.to_event()))
+    }
+}
+
+impl Deserialize for MaybeEvent {
+    fn deserialize<D: Deserializer>(deserializer: &mut D) -> Result<MaybeEvent, D::Error> {
+        deserializer.deserialize_map(EventVisitor)
+    }
+}
+
+impl Deserialize for Event {
+    fn deserialize<D: Deserializer>(deserializer: &mut D) -> Result<Event, D::Error> {
+        match try!(MaybeEvent::deserialize(deserializer)) {
+            MaybeEvent(Ok(event)) => Ok(event),
+            MaybeEvent(Err(e)) => Err(de::Error::custom(e.to_string())),
+        }
+    }
+}
+
+impl Serialize for Event {
+    fn serialize<S: Serializer>(&self, serializer: &mut S) -> Result<(), S::Error> {
+        let fields = [("botId", self.bot_id()), ("source", self.source()), ("damage", self.damage())];
+        let mut state = try!(serializer.serialize_map(None));
+        try!(serializer.serialize_map_key(&mut state, "event"));
+        try!(serializer.serialize_map_value(&mut state, self.kind().name()));
+        for &(key, value) in fields.iter().filter(|&&(_, value)| value.is_some()) {
+            try!(serializer.serialize_map_key(&mut state, key));
+            try!(serializer.serialize_map_value(&mut state, value));
+        }
+        if let Some(pos) = self.pos() {
+            try!(serializer.serialize_map_key(&mut state, "pos"));
+            try!(serializer.serialize_map_value(&mut state, pos));
+        }
+        serializer.serialize_map_end(state)
+    }
+}
+
+impl Serialize for MaybeEvent {
+    fn serialize<S: Serializer>(&self, serializer: &mut S) -> Result<(), S::Error> {
+        match self.0 {
+            Ok(ref event) => event.serialize(serializer),
+            Err(ref e) => Err(ser::Error::custom(format!("Can't write out a malformed event: {}", e))),
+        }
+    }
+}
+
+impl IncomingEvents {
+    // Every event of the message that is one, and what was wrong with the rest
+    pub fn parse_events(&self) -> (Vec<Event>, Vec<EventError>) {
+        let mut events: Vec<Event> = Vec::new();
+        let mut errors: Vec<EventError> = Vec::new();
+        for ev in &self.events {
+            match ev.0 {
+                Ok(event) => events.push(event),
+                Err(ref e) => errors.push(e.clone()),
+            }
+        }
+        (events, errors)
+    }
+}
+
+// Parses json into T and writes it out again, which must give back the same JSON
+#[cfg(test)]
+fn assert_round_trip<T: serde::Serialize + serde::Deserialize>(json: &str) {
+    let parsed: T = serde_json::from_str(json).unwrap();
+    let written = serde_json::to_string(&parsed).unwrap();
+    let expected: serde_json::Value = serde_json::from_str(json).unwrap();
+    let got: serde_json::Value = serde_json::from_str(&written).unwrap();
+    assert_eq!(got, expected);
+}
+
+#[cfg(test)]
+static CONFIG: &'static str = r#"{"bots": 3, "fieldRadius": 14, "move": 2, "startHp": 10, "cannon": 1, "radar": 3,
+    "see": 2, "maxCount": 200, "asteroids": 0, "loopTime": 300, "noWait": false}"#;
+
+#[test]
+fn client_messages_round_trip() {
+    assert_round_trip::<JoinMessage>(r#"{"type": "join", "teamName": "Serenity"}"#);
+    assert_round_trip::<ActionsMessage>(r#"{"type": "actions", "roundId": 3, "actions": [
+        {"type": "move", "botId": 0, "pos": {"x": 1, "y": -1}},
+        {"type": "radar", "botId": 1, "pos": {"x": -4, "y": 7}},
+        {"type": "cannon", "botId": 2, "pos": {"x": 0, "y": 14}}]}"#);
+
+    // What we send must be exactly what the server expects
+    let actions = ActionsMessage {
+        event_type: "actions".to_string(),
+        round_id: 2,
+        actions: vec![Action { bot_id: 1, kind: ActionKind::Cannon, pos: Pos::new(-1, 3) }],
+    };
+    assert_eq!(serde_json::to_string(&actions).unwrap(),
+               r#"{"type":"actions","roundId":2,"actions":[{"type":"cannon","botId":1,"pos":{"x":-1,"y":3}}]}"#);
+}
+
+#[test]
+fn server_messages_round_trip() {
+    let you = r#"{"name": "Serenity", "teamId": 1, "bots": [
+        {"botId": 3, "name": "Mal", "teamId": 1, "alive": true, "pos": {"x": 2, "y": -3}, "hp": 10},
+        {"botId": 4, "name": "Zoe", "teamId": 1, "alive": false, "pos": {"x": 0, "y": 0}, "hp": 0}]}"#;
+    // Enemy bots come without hp and pos
+    let other_teams = r#"[{"name": "Reavers", "teamId": 0, "bots": [
+        {"botId": 0, "name": "Reaver 1", "teamId": 0, "alive": true}]}]"#;
+
+    assert_round_trip::<Config>(CONFIG);
+    assert_round_trip::<IncomingConnected>(&format!(r#"{{"type": "connected", "teamId": 1, "config": {}}}"#, CONFIG));
+    assert_round_trip::<Start>(&format!(r#"{{"type": "start", "you": {}, "config": {}, "otherTeams": {}}}"#,
+                                        you, CONFIG, other_teams));
+    assert_round_trip::<IncomingEnd>(&format!(r#"{{"type": "end", "winnerTeamId": -1, "you": {}}}"#, you));
+    assert_round_trip::<IncomingError>(r#"{"type": "error", "data": "Already started"}"#);
+
+    let events = r#"[
+        {"event": "hit", "botId": 0, "source": 3},
+        {"event": "die", "botId": 0},
+        {"event": "see", "source": 3, "botId": 1, "pos": {"x": 4, "y": -2}},
+        {"event": "seeAsteroid", "pos": {"x": 1, "y": 1}},
+        {"event": "radarEcho", "pos": {"x": 5, "y": 5}},
+        {"event": "detected", "botId": 3},
+        {"event": "damaged", "botId": 4, "damage": 2},
+        {"event": "move", "botId": 3, "pos": {"x": 2, "y": -3}},
+        {"event": "noaction", "botId": 4}]"#;
+    assert_round_trip::<IncomingEvents>(&format!(
+        r#"{{"type": "events", "roundId": 12, "config": {}, "you": {}, "otherTeams": {}, "events": {}}}"#,
+        CONFIG, you, other_teams, events));
+}
+
+#[test]
+fn events_are_told_apart_by_their_name() {
+    let events: Vec<Event> = serde_json::from_str(r#"[
+        {"event": "damaged", "botId": 4, "damage": 2},
+        {"event": "radarEcho", "pos": {"x": 5, "y": 5}}]"#).unwrap();
+    assert_eq!(events, vec![Event::Damaged(DamagedEvent { bot_id: 4, damage: 2 }),
+                            Event::Echo(EchoEvent { pos: Pos::new(5, 5) })]);
+    assert_eq!(events.iter().map(|ev| ev.kind().name()).collect::<Vec<_>>(), vec![DAMAGED, RADARECHO]);
+    assert_eq!((events[0].bot_id(), events[1].pos()), (Some(4), Some(Pos::new(5, 5))));
+    assert_eq!(serde_json::to_string(&events[0]).unwrap(), r#"{"event":"damaged","botId":4,"damage":2}"#);
+
+    // Fields an event doesn't have are no reason to turn it down
+    let see: Event = serde_json::from_str(r#"{"event": "see", "source": 3, "botId": 1, "pos": {"x": 4, "y": -2}, "hp": 3}"#).unwrap();
+    assert_eq!(see, Event::See(SeeEvent { source: 3, bot_id: 1, pos: Pos::new(4, -2) }));
+
+    assert!(serde_json::from_str::<Event>(r#"{"event": "hit", "botId": 1}"#).is_err());
+    assert!(serde_json::from_str::<Action>(r#"{"type": "teleport", "botId": 1, "pos": {"x": 0, "y": 0}}"#).is_err());
+}
+
+#[test]
+fn malformed_events_are_skipped_one_by_one() {
+    let message: IncomingEvents = serde_json::from_str(&format!(
+        r#"{{"type": "events", "roundId": 1, "config": {}, "you": {{"name": "Serenity", "teamId": 1, "bots": []}},
+            "otherTeams": [], "events": [
+            {{"event": "hit", "botId": 1}},
+            {{"event": "teleport", "botId": 1}},
+            {{"event": "die", "botId": 2}}]}}"#, CONFIG)).unwrap();
+
+    // A hit needs to say who shot, and there's no such event as a teleport
+    let (events, errors) = message.parse_events();
+    assert_eq!(events, vec![Event::Die(DieEvent { bot_id: 2 })]);
+    assert_eq!(errors, vec![EventError::MissingField { event: HIT.to_string(), field: "source" },
+                            EventError::UnknownEvent("teleport".to_string())]);
+}
+
+#[test]
+fn message_type_is_read_from_any_message() {
+    let message: IncomingMessage = serde_json::from_str(r#"{"type": "end", "winnerTeamId": 0, "you": {}}"#).unwrap();
+    assert_eq!(message.event_type, "end");
+}
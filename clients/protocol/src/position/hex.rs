@@ -0,0 +1,305 @@
+// Hex grid geometry, mostly after http://www.redblobgames.com/grids/hexagons/
+//
+// Pos is in axial coordinates. As cube coordinates it's (x, -x - y, y), which is how
+// distance and clamp already treat it: x and y are cube x and z, and cube y is what's left.
+
+use std::collections::VecDeque;
+use std::ops::{Neg, Sub};
+use position::Pos;
+
+// The six neighbors of origo, going around it counterclockwise starting from the right
+pub const DIRECTIONS: [Pos; 6] = [
+    Pos { x: 1, y: 0 },
+    Pos { x: 1, y: -1 },
+    Pos { x: 0, y: -1 },
+    Pos { x: -1, y: 0 },
+    Pos { x: -1, y: 1 },
+    Pos { x: 0, y: 1 },
+];
+
+// A hex in cube coordinates, x + y + z is always 0
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Cube {
+    pub x: i16,
+    pub y: i16,
+    pub z: i16,
+}
+
+impl Cube {
+    pub fn new(x: i16, y: i16, z: i16) -> Cube {
+        debug_assert!(x + y + z == 0, "Cube coordinates must sum to 0");
+        Cube { x: x, y: y, z: z }
+    }
+
+    pub fn to_pos(self) -> Pos {
+        Pos { x: self.x, y: self.z }
+    }
+}
+
+// Rounds a point in fractional cube coordinates to the hex it's in. Simply rounding would
+// work in most cases, but can give coordinates that don't add up to 0, so the coordinate
+// that changed the most is fixed from the other two.
+// http://www.redblobgames.com/grids/hexagons/#rounding
+pub fn round_cube(x: f32, y: f32, z: f32) -> Pos {
+    let mut rx = x.round() as i16;
+    let ry = y.round() as i16;
+    let mut rz = z.round() as i16;
+
+    let x_diff: f32 = (rx as f32 - x).abs();
+    let y_diff: f32 = (ry as f32 - y).abs();
+    let z_diff: f32 = (rz as f32 - z).abs();
+
+    if x_diff > y_diff && x_diff > z_diff {
+        rx = -ry - rz;
+    } else if y_diff > z_diff {
+        // ry would be fixed, but it's not part of Pos
+    } else {
+        rz = -rx - ry;
+    }
+    Pos { x: rx, y: rz }
+}
+
+impl Pos {
+    pub fn to_cube(self) -> Cube {
+        Cube::new(self.x, -self.x - self.y, self.y)
+    }
+
+    pub fn from_cube(cube: Cube) -> Pos {
+        cube.to_pos()
+    }
+
+    pub fn scale(self, factor: i16) -> Pos {
+        Pos { x: self.x * factor, y: self.y * factor }
+    }
+
+    // The neighbor in one of DIRECTIONS, any index wraps around
+    pub fn neighbor(self, direction: usize) -> Pos {
+        self + DIRECTIONS[direction % 6]
+    }
+
+    // The hexes exactly radius away, going around counterclockwise. Just self for 0.
+    pub fn ring(self, radius: i16) -> Vec<Pos> {
+        if radius <= 0 {
+            return vec![self];
+        }
+        let mut result: Vec<Pos> = Vec::new();
+        let mut pos = self + DIRECTIONS[4].scale(radius);
+        for direction in 0..6 {
+            for _ in 0..radius {
+                result.push(pos);
+                pos = pos.neighbor(direction);
+            }
+        }
+        result
+    }
+
+    // Self and then every ring out to radius, so the same hexes as area but ordered by distance
+    pub fn spiral(self, radius: i16) -> Vec<Pos> {
+        let mut result: Vec<Pos> = Vec::new();
+        for ring in 0..radius + 1 {
+            result.append(&mut self.ring(ring));
+        }
+        result
+    }
+
+    // The hexes a straight line from self to other passes through, both ends included
+    pub fn line_to(self, other: Pos) -> Vec<Pos> {
+        let steps = self.distance(other);
+        if steps == 0 {
+            return vec![self];
+        }
+        let (a, b) = (self.to_cube(), other.to_cube());
+        // Nudged off the edges between hexes so the line doesn't go back and forth on them
+        let (ax, ay, az) = (a.x as f32 + 1e-6, a.y as f32 + 2e-6, a.z as f32 - 3e-6);
+        let (bx, by, bz) = (b.x as f32 + 1e-6, b.y as f32 + 2e-6, b.z as f32 - 3e-6);
+        (0..steps + 1)
+            .map(|step| {
+                let t = step as f32 / steps as f32;
+                round_cube(ax + (bx - ax) * t, ay + (by - ay) * t, az + (bz - az) * t)
+            })
+            .collect()
+    }
+
+    // Turned around origo by 60 degrees counterclockwise, steps times. Negative steps turn clockwise.
+    pub fn rotate(self, steps: i16) -> Pos {
+        let mut cube = self.to_cube();
+        for _ in 0..((steps % 6) + 6) % 6 {
+            cube = Cube::new(-cube.y, -cube.z, -cube.x);
+        }
+        cube.to_pos()
+    }
+
+    // Mirrored over the line through origo where cube x is kept, i.e. the other two swap
+    pub fn reflect_x(self) -> Pos {
+        let cube = self.to_cube();
+        Cube::new(cube.x, cube.z, cube.y).to_pos()
+    }
+
+    pub fn reflect_y(self) -> Pos {
+        let cube = self.to_cube();
+        Cube::new(cube.z, cube.y, cube.x).to_pos()
+    }
+
+    pub fn reflect_z(self) -> Pos {
+        let cube = self.to_cube();
+        Cube::new(cube.y, cube.x, cube.z).to_pos()
+    }
+
+    pub fn is_on_field(self, field_radius: i16) -> bool {
+        self.distance(Pos::origo()) <= field_radius
+    }
+
+    // Every hex a bot can walk to in at most steps moves of one hex, without leaving the
+    // field or stepping on a blocked hex. Self included. The server lets bots jump over
+    // anything, so this is for plans that go through several rounds of moves.
+    pub fn reachable<F: Fn(Pos) -> bool>(self, steps: i16, field_radius: i16, blocked: F) -> Vec<Pos> {
+        let mut result: Vec<Pos> = vec![self];
+        let mut queue: VecDeque<(Pos, i16)> = VecDeque::new();
+        queue.push_back((self, 0));
+
+        while let Some((pos, distance)) = queue.pop_front() {
+            if distance == steps {
+                continue;
+            }
+            for direction in 0..6 {
+                let next = pos.neighbor(direction);
+                if next.is_on_field(field_radius) && !blocked(next) && !result.contains(&next) {
+                    result.push(next);
+                    queue.push_back((next, distance + 1));
+                }
+            }
+        }
+        result
+    }
+}
+
+// Every hex on a field of this radius, from the middle out
+pub fn field(field_radius: i16) -> Vec<Pos> {
+    Pos::origo().spiral(field_radius)
+}
+
+// The outermost hexes of the field
+pub fn field_boundary(field_radius: i16) -> Vec<Pos> {
+    Pos::origo().ring(field_radius)
+}
+
+impl Sub for Pos {
+    type Output = Pos;
+
+    fn sub(self, other: Pos) -> Pos {
+        Pos { x: self.x - other.x, y: self.y - other.y }
+    }
+}
+
+impl Neg for Pos {
+    type Output = Pos;
+
+    fn neg(self) -> Pos {
+        Pos { x: -self.x, y: -self.y }
+    }
+}
+
+// Everything within radius of center by brute force over the bounding box
+#[cfg(test)]
+fn brute_force_area(center: Pos, radius: i16) -> Vec<Pos> {
+    let mut result: Vec<Pos> = Vec::new();
+    for x in center.x - radius..center.x + radius + 1 {
+        for y in center.y - radius..center.y + radius + 1 {
+            if center.distance(Pos::new(x, y)) <= radius {
+                result.push(Pos::new(x, y));
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+fn test_centers() -> Vec<Pos> {
+    Pos::origo().area(4)
+}
+
+#[cfg(test)]
+fn sorted(mut positions: Vec<Pos>) -> Vec<(i16, i16)> {
+    let mut pairs: Vec<(i16, i16)> = positions.drain(..).map(|pos| (pos.x, pos.y)).collect();
+    pairs.sort();
+    pairs
+}
+
+#[test]
+fn hex_rings_and_spirals_match_brute_force() {
+    for center in test_centers() {
+        for radius in 0..6 {
+            let ring = center.ring(radius);
+            let expected: Vec<Pos> = brute_force_area(center, radius).into_iter()
+                .filter(|pos| center.distance(*pos) == radius)
+                .collect();
+            assert_eq!(sorted(ring.clone()), sorted(expected));
+            // Each hex of a ring is next to the one before it, all the way around
+            for (index, pos) in ring.iter().enumerate().skip(1) {
+                assert_eq!(pos.distance(ring[index - 1]), 1);
+            }
+
+            let spiral = center.spiral(radius);
+            assert_eq!(sorted(spiral.clone()), sorted(brute_force_area(center, radius)));
+            assert_eq!(sorted(center.area(radius)), sorted(brute_force_area(center, radius)));
+            assert_eq!(sorted(center.neighbors(radius)),
+                       sorted(brute_force_area(center, radius).into_iter().filter(|pos| *pos != center).collect()));
+            assert!(spiral.windows(2).all(|pair| center.distance(pair[0]) <= center.distance(pair[1])));
+        }
+    }
+}
+
+#[test]
+fn hex_lines_are_connected_and_shortest() {
+    let centers = test_centers();
+    for &from in &centers {
+        for to in Pos::origo().area(6) {
+            let line = from.line_to(to);
+            assert_eq!(line.len() as i16, from.distance(to) + 1);
+            assert_eq!((line[0], line[line.len() - 1]), (from, to));
+            assert!(line.windows(2).all(|pair| pair[0].distance(pair[1]) == 1), "{} to {}: {:?}", from, to, line);
+        }
+    }
+}
+
+#[test]
+fn hex_rotations_and_reflections_keep_distances() {
+    let positions = Pos::origo().area(5);
+    for &a in &positions {
+        assert_eq!(Pos::from_cube(a.to_cube()), a);
+        assert_eq!(a.rotate(6), a);
+        assert_eq!(a.rotate(1).rotate(-1), a);
+        assert_eq!(a.rotate(3), -a);
+        assert_eq!(a.reflect_x().reflect_x(), a);
+        for &b in positions.iter().filter(|b| b.distance(Pos::origo()) <= 2) {
+            let d = a.distance(b);
+            assert_eq!(a.rotate(1).distance(b.rotate(1)), d);
+            assert_eq!(a.rotate(-2).distance(b.rotate(-2)), d);
+            assert_eq!(a.reflect_x().distance(b.reflect_x()), d);
+            assert_eq!(a.reflect_y().distance(b.reflect_y()), d);
+            assert_eq!(a.reflect_z().distance(b.reflect_z()), d);
+        }
+    }
+    assert_eq!(DIRECTIONS[0].rotate(1), DIRECTIONS[1]);
+}
+
+#[test]
+fn hex_field_and_reachable_sets() {
+    assert_eq!(field(14).len(), 631);
+    assert_eq!(field_boundary(14).len(), 84);
+    assert!(field_boundary(14).iter().all(|pos| pos.distance(Pos::origo()) == 14));
+
+    // Without anything in the way, walking gets as far as the area
+    let free = Pos::new(2, 1).reachable(3, 14, |_| false);
+    assert_eq!(sorted(free), sorted(Pos::new(2, 1).area(3)));
+
+    // Walled in by a ring, we can't get out however far we walk
+    let wall = Pos::origo().ring(2);
+    let inside = Pos::origo().reachable(10, 14, |pos| wall.contains(&pos));
+    assert_eq!(sorted(inside), sorted(Pos::origo().area(1)));
+
+    // The field edge stops us too
+    let corner = Pos::new(14, 0).reachable(2, 14, |_| false);
+    assert!(corner.iter().all(|pos| pos.is_on_field(14)));
+    assert_eq!(sorted(corner), sorted(brute_force_area(Pos::new(14, 0), 2).into_iter().filter(|pos| pos.is_on_field(14)).collect()));
+}
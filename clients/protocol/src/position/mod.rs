@@ -1,13 +1,11 @@
-extern crate serde;
-extern crate serde_json;
-
 use std::cmp;
 use std::ops::Add;
-use util;
 use std::fmt;
 
 include!(concat!(env!("OUT_DIR"), "/position.rs"));
 
+pub mod hex;
+
 impl Pos {
     #[allow(dead_code)]
     pub fn new(x: i16, y: i16) -> Pos {
@@ -27,30 +25,23 @@ impl Pos {
         cmp::max( cmp::max(dx.abs(), dy.abs()), dz.abs())
     }
 
-    pub fn random_spread(&self) -> Pos {
-        Pos {
-            x: self.x + util::get_rand_range(-1, 1),
-            y: self.y + util::get_rand_range(-1, 1)
-        }
-    }
-
+    // All positions within radius, except self
     #[allow(dead_code)]
     pub fn neighbors(&self, radius: i16) -> Vec<Pos> {
+        self.area(radius)
+            .into_iter()
+            .filter(|pos| pos != self)
+            .collect()
+    }
+
+    // All positions within radius, including self
+    pub fn area(&self, radius: i16) -> Vec<Pos> {
         let mut result: Vec<Pos> = Vec::new();
-        let x_min = self.x - radius;
-        let x_max = self.x + radius;
-        let y_min = self.y - radius;
-        let y_max = self.y + radius;
-
-        for x in x_min..x_max {
-            for y in y_min..y_max {
-                let new_pos = Pos { x: x, y: y };
-                if self.distance(new_pos) <= radius && *self != new_pos {
-                    result.push(new_pos);
-                }
+        for dx in -radius..radius + 1 {
+            for dy in cmp::max(-radius, -dx - radius)..cmp::min(radius, -dx + radius) + 1 {
+                result.push(Pos { x: self.x + dx, y: self.y + dy });
             }
         }
-
         return result;
     }
 
@@ -80,40 +71,11 @@ impl Pos {
             let cz = self.y as f32 * t;
 
             // We need to round the floating point location to nearest hex
-            return self.round_cube_to_nearest_hex( cx, cy, cz);
+            return hex::round_cube(cx, cy, cz);
         }
 
         return self.clone();
     }
-
-    #[allow(dead_code)]
-    fn round_cube_to_nearest_hex( &self, x: f32, y: f32, z: f32 ) -> Pos {
-        // Simply rounding would work in most of the cases.
-        // The extra logic is there to handle few special cases,
-        // where a position would round to wrong hex and possibly out of area.
-        // The logic is explained here:
-        // http://www.redblobgames.com/grids/hexagons/#rounding
-
-        let mut rx = x.round() as i16;
-        let ry = y.round() as i16;
-        let mut rz = z.round() as i16;
-
-        let x_diff: f32 = {rx as f32 - x}.abs();
-        let y_diff: f32 = {ry as f32 - y}.abs();
-        let z_diff: f32 = {rz as f32 - z}.abs();
-
-        if x_diff > y_diff && x_diff > z_diff {
-            rx = -ry - rz;
-        } else if y_diff > z_diff {
-            // Do nothing apparently? ry = -rx - rz;
-        } else {
-            rz = -rx - ry;
-        }
-        Pos {
-            x: rx as i16,
-            y: rz as i16
-        }
-    }
 }
 
 impl fmt::Display for Pos {
@@ -129,3 +91,17 @@ impl Add for Pos {
         Pos { x: self.x + other.x, y: self.y + other.y }
     }
 }
+
+#[test]
+fn check_distance() {
+    assert!(Pos { x: 0, y: 0}.distance(Pos {x: 1, y: 0}) == 1);
+    assert!(Pos { x: 0, y: 0}.distance(Pos {x: 1, y: 3}) == 4);
+    assert!(Pos { x: 0, y: 0}.distance(Pos {x: 0, y: 5}) == 5);
+}
+
+#[test]
+fn check_positions_within() {
+    let pos = Pos { x: 0, y: 0};
+    assert!( pos.area(1).len() == 7);
+    assert!( pos.area(2).len() == 19);
+}
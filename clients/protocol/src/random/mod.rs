@@ -0,0 +1,57 @@
+// The simplest player there is: every bot fires, moves or radars at random. clients/rust plays
+// with it and serenity's tournament plays against it. It draws from a seeded generator, so with
+// the same seed it answers a game the same way every time.
+use rand::{Rng, SeedableRng, StdRng};
+use messages::{Action, ActionKind, Config, Team};
+use position::Pos;
+
+pub struct RandomAi {
+    rng: StdRng,
+}
+
+impl RandomAi {
+    pub fn new(seed: &[usize]) -> RandomAi {
+        RandomAi { rng: SeedableRng::from_seed(seed) }
+    }
+
+    // An action for each bot of ours that's alive
+    pub fn respond(&mut self, config: &Config, you: &Team) -> Vec<Action> {
+        let mut actions = Vec::new();
+        for bot in you.bots.iter().filter(|bot| bot.alive) {
+            let (kind, pos) = match self.rng.gen_range(0, 3) {
+                0 => (ActionKind::Cannon, self.random_pos(config)),
+                // Our own bots always come with a position
+                1 => (ActionKind::Move, *self.rng.choose(&bot.pos.unwrap().area(config.moves_allowed)).unwrap()),
+                _ => (ActionKind::Radar, self.random_pos(config)),
+            };
+            actions.push(Action { bot_id: bot.bot_id, kind: kind, pos: pos });
+        }
+        actions
+    }
+
+    fn random_pos(&mut self, config: &Config) -> Pos {
+        let radius = config.field_radius;
+        Pos::new(self.rng.gen_range(-radius, radius), self.rng.gen_range(-radius, radius))
+    }
+}
+
+#[test]
+fn same_seed_same_actions() {
+    use messages::Bot;
+
+    let config = Config { bots: 2, field_radius: 14, moves_allowed: 2, start_hp: 10, cannon: 1, radar: 3, see: 2,
+                          max_count: 200, asteroids: 0, loop_time: 300, no_wait: false };
+    let bot = |bot_id: i16, alive: bool| Bot { bot_id: bot_id, name: format!("Bot {}", bot_id), team_id: 0, alive: alive,
+                                             pos: Some(Pos::new(bot_id, 0)), hp: Some(10) };
+    let you = Team { name: "Rust".to_string(), team_id: 0, bots: vec![bot(0, true), bot(1, false), bot(2, true)] };
+
+    let play = |seed: usize| {
+        let mut ai = RandomAi::new(&[seed]);
+        (0..10).map(|_| ai.respond(&config, &you)).collect::<Vec<_>>()
+    };
+    let rounds = play(1);
+    assert!(rounds.iter().all(|actions| actions.iter().map(|action| action.bot_id).collect::<Vec<_>>() == vec![0, 2]));
+    assert!(rounds.iter().flat_map(|actions| actions.iter()).all(|action| action.pos.distance(Pos::origo()) <= 28));
+    assert_eq!(format!("{:?}", rounds), format!("{:?}", play(1)));
+    assert!(format!("{:?}", rounds) != format!("{:?}", play(2)));
+}
@@ -0,0 +1,24 @@
+// The names the server uses for message, action and event types. Spelling one wrong means
+// the server doesn't understand us, so they're here for the compiler to check.
+
+pub const CONNECTED: &'static str = "connected";
+pub const JOIN: &'static str = "join";
+pub const START: &'static str = "start";
+pub const ACTIONS: &'static str = "actions";
+pub const END: &'static str = "end";
+pub const EVENTS: &'static str = "events";
+// The server turned us down, e.g. joining a game that already started
+pub const ERROR: &'static str = "error";
+
+pub const CANNON: &'static str = "cannon";
+pub const RADAR: &'static str = "radar";
+pub const MOVE: &'static str = "move";
+
+pub const HIT: &'static str = "hit";
+pub const DIE: &'static str = "die";
+pub const SEE: &'static str = "see";
+pub const SEEASTEROID: &'static str = "seeAsteroid";
+pub const RADARECHO: &'static str = "radarEcho";
+pub const DETECTED: &'static str = "detected";
+pub const DAMAGED: &'static str = "damaged";
+pub const NOACTION: &'static str = "noaction";
@@ -0,0 +1,12 @@
+extern crate serde_codegen;
+
+use std::env;
+use std::path::Path;
+
+pub fn main() {
+    let out_dir = env::var_os("OUT_DIR").unwrap();
+
+    for &(src, dst) in &[("src/webgame/mod.rs.in", "webgame.rs"), ("src/record/mod.rs.in", "record.rs")] {
+        serde_codegen::expand(&Path::new(src), &Path::new(&out_dir).join(dst)).unwrap();
+    }
+}
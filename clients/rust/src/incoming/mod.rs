@@ -16,211 +16,53 @@
  *  You should have received a copy of the GNU General Public License
  *  along with Rust Tyckiting client.  If not, see <http://www.gnu.org/licenses/>.
  */
-use super::{Position, GameConfig};
-use serde::json::{self, Value};
-
-macro_rules! getString {
-    ($obj:ident, $field:expr) => {
-        String::from_str($obj.get($field).unwrap().as_string().unwrap())
-    }
-}
+// The messages themselves come from the protocol crate, shared with serenity
+pub use tyckiting_protocol::messages::{Config, Bot, Team, Event, IncomingMessage, IncomingConnected, Start,
+                                       IncomingEvents, IncomingEnd, IncomingError, JoinMessage, Action, ActionKind,
+                                       ActionsMessage};
+use tyckiting_protocol::strings::{CONNECTED, START, END, EVENTS, ERROR};
+use serde_json as json;
 
 pub fn parse_message(msg: String) -> Message {
-    let value: Value = json::from_str(&msg).unwrap();
-    let obj = value.as_object().unwrap();
-    let message = match getString!(obj, "type").as_ref() {
-        "connected" => Message::ConnectedMessage(json::from_str(&msg).unwrap()),
-        "start" => Message::StartMessage(json::from_str(&msg).unwrap()),
-        "end" => Message::EndMessage(json::from_str(&msg).unwrap()),
-        "events" => Message::EventsMessage(parse_events_message(&value)),
+    let kind: IncomingMessage = json::from_str(&msg).unwrap();
+    let message = match kind.event_type.as_ref() {
+        CONNECTED => Message::ConnectedMessage(json::from_str(&msg).unwrap()),
+        START => Message::StartMessage(json::from_str(&msg).unwrap()),
+        END => Message::EndMessage(json::from_str(&msg).unwrap()),
+        EVENTS => Message::EventsMessage(json::from_str(&msg).unwrap()),
+        // e.g. we joined a game that had already started, nothing more will come
+        ERROR => {
+            let error: IncomingError = json::from_str(&msg).unwrap();
+            panic!("The server refused us: {}", error.data)
+        }
         _ => panic!("Couldn't parse message")
     };
     message
 }
 
-fn parse_events_message(value: &Value) -> EventsMessage {
-    let obj = value.as_object().unwrap();
-    EventsMessage {
-        type_: getString!(obj, "type"),
-        round_id: obj.get("roundId").unwrap().as_u64().unwrap() as u32,
-        config: json::value::from_value(obj.get("config").unwrap().clone()).unwrap(),
-        you: json::value::from_value(obj.get("you").unwrap().clone()).unwrap(),
-        other_teams: json::value::from_value(obj.get("otherTeams").unwrap().clone()).unwrap(),
-        events: obj.get("events").unwrap().clone().as_array().unwrap().iter().map(|ev| parse_event(ev.clone())).collect()
-    }
-}
-
-fn parse_event(value: Value) -> Event {
-    let value1 = value.clone();
-    let obj = value1.as_object().unwrap();
-    match getString!(obj, "event").as_ref() {
-        "damaged" => Event::DamagedEvent(json::value::from_value(value).unwrap()),
-        "hit" => Event::HitEvent(json::value::from_value(value).unwrap()),
-        "die" => Event::DieEvent(json::value::from_value(value).unwrap()),
-        "see" => Event::SeeEvent(json::value::from_value(value).unwrap()),
-        "seeAsteroid" => Event::SeeAsteroidEvent(json::value::from_value(value).unwrap()),
-        "radarEcho" => Event::RadarEchoEvent(json::value::from_value(value).unwrap()),
-        "detected" => Event::DetectedEvent(json::value::from_value(value).unwrap()),
-        "noaction" => Event::NoActionEvent(json::value::from_value(value).unwrap()),
-        "move" => Event::MoveEvent(json::value::from_value(value).unwrap()),
-        _ => panic!("Couldn't parse event")
+#[test]
+fn test_parse_message() {
+    let msg = "{\"type\":\"end\",\"winnerTeamId\":-1,\"you\":{\"name\":\"Team Rust\",\"teamId\":1,\"bots\":[]}}".to_string();
+    match parse_message(msg) {
+        Message::EndMessage(end) => assert!(end.winner_team_id == -1 && end.you.team_id == 1),
+        _ => panic!("Test failed")
     }
 }
 
 #[test]
 fn test_parse_event() {
     let event_json = "{\"event\":\"noaction\",\"botId\":4}".to_string();
-    let event_value: Value = json::from_str(&event_json).unwrap();
-    let event_struct = parse_event(event_value);
+    let event_struct: Event = json::from_str(&event_json).unwrap();
     match event_struct {
-        Event::NoActionEvent(ev) => assert!(ev.bot_id == 4),
+        Event::Noaction(ev) => assert!(ev.bot_id == 4),
         _ => panic!("Test failed")
     }
 }
 
-
-#[derive(Debug, Deserialize, Default)]
-pub struct Bot {
-    #[serde(rename="botId")] pub bot_id: u32,
-    pub name: String,
-    #[serde(rename="teamId")] pub team_id: u32,
-    pub hp: i32,
-    pub alive: bool,
-    pub pos: Position
-}
-
-#[derive(Debug, Deserialize, Default)]
-pub struct BotNoPosNoHp {
-    #[serde(rename="botId")] pub bot_id: u32,
-    pub name: String,
-    #[serde(rename="teamId")] pub team_id: u32,
-    pub hp: Option<i32>,
-    pub alive: bool,
-    pub pos: Option<Position>
-}
-
-#[derive(Debug, Deserialize, Default)]
-pub struct Team {
-    pub name: String,
-    #[serde(rename="teamId")] pub team_id: u32,
-    pub bots: Vec<Bot>
-}
-
-#[derive(Debug, Deserialize, Default)]
-pub struct TeamNoPosNoHp {
-    pub name: String,
-    #[serde(rename="teamId")] pub team_id: u32,
-    pub bots: Vec<BotNoPosNoHp>
-}
-
-#[derive(Debug, Deserialize)]
-pub struct DamagedEvent {
-    event: String,
-    #[serde(rename="botId")] pub bot_id: u32,
-    pub damage: u32
-}
-
-#[derive(Debug, Deserialize)]
-pub struct HitEvent {
-    event: String,
-    #[serde(rename="botId")] pub bot_id: u32,
-    pub source: u32
-}
-
-#[derive(Debug, Deserialize)]
-pub struct DieEvent {
-    event: String,
-    #[serde(rename="botId")] pub bot_id: u32
-}
-
-#[derive(Debug, Deserialize)]
-pub struct SeeEvent {
-    event: String,
-    #[serde(rename="botId")] pub bot_id: u32,
-    pub source: u32,
-    pub pos: Position
-}
-
-#[derive(Debug, Deserialize)]
-pub struct SeeAsteroidEvent {
-    event: String,
-    pub pos: Position
-}
-
-#[derive(Debug, Deserialize)]
-pub struct RadarEchoEvent {
-    event: String,
-    pub pos: Position
-}
-
-#[derive(Debug, Deserialize)]
-pub struct DetectedEvent {
-    event: String,
-    #[serde(rename="botId")] pub bot_id: u32
-}
-
-#[derive(Debug, Deserialize)]
-pub struct NoActionEvent {
-    event: String,
-    #[serde(rename="botId")] pub bot_id: u32
-}
-
-#[derive(Debug, Deserialize)]
-pub struct MoveEvent {
-    event: String,
-    #[serde(rename="botId")] pub bot_id: u32,
-    pub pos: Position
-}
-
-#[derive(Debug, Deserialize)]
-pub enum Event {
-    DamagedEvent(DamagedEvent),
-    HitEvent(HitEvent),
-    DieEvent(DieEvent),
-    SeeEvent(SeeEvent),
-    SeeAsteroidEvent(SeeEvent),
-    RadarEchoEvent(RadarEchoEvent),
-    DetectedEvent(DetectedEvent),
-    NoActionEvent(NoActionEvent),
-    MoveEvent(MoveEvent)
-}
-
-#[derive(Debug, Deserialize)]
-struct ConnectedMessage {
-    #[serde(rename="type")] type_: String,
-    #[serde(rename="teamId")] team_id: u32,
-    config: GameConfig
-}
-
-#[derive(Debug, Deserialize)]
-pub struct StartMessage {
-    #[serde(rename="type")] type_: String,
-    pub config: GameConfig,
-    pub you: Team,
-    #[serde(rename="otherTeams")] pub other_teams: Vec<TeamNoPosNoHp>
-}
-
-#[derive(Debug, Deserialize)]
-pub struct EndMessage {
-    #[serde(rename="type")] type_: String,
-    #[serde(rename="winnerTeamId")] pub winner_team_id: Option<u32>,
-    pub you: Team
-}
-
-#[derive(Debug, Deserialize)]
-pub struct EventsMessage {
-    #[serde(rename="type")] type_: String,
-    #[serde(rename="roundId")] pub round_id: u32,
-    pub config: GameConfig,
-    pub you: Team,
-    #[serde(rename="otherTeams")] pub other_teams: Vec<TeamNoPosNoHp>,
-    pub events: Vec<Event>
-}
-
-#[derive(Debug, Deserialize)]
+#[derive(Debug)]
 pub enum Message {
-    ConnectedMessage(ConnectedMessage),
-    StartMessage(StartMessage),
-    EndMessage(EndMessage),
-    EventsMessage(EventsMessage)
+    ConnectedMessage(IncomingConnected),
+    StartMessage(Start),
+    EndMessage(IncomingEnd),
+    EventsMessage(IncomingEvents)
 }
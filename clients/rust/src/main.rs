@@ -1,5 +1,3 @@
-#![feature(custom_derive, custom_attribute, plugin, collections, scoped)]
-#![plugin(serde_macros)]
 /** Rust Tyckiting client - A websocket client for a fight to kill all other bots
  *  Copyright Futurice Oy (2015)
  *
@@ -21,38 +19,44 @@
 
 extern crate websocket;
 extern crate serde;
+extern crate serde_json;
+extern crate tyckiting_protocol;
 extern crate rustc_serialize;
 extern crate docopt;
 extern crate hyper;
+extern crate time;
 
 mod incoming;
 mod ai;
+mod record;
+mod webgame;
 
 use std::thread;
-use std::cmp::{max, min};
-use std::io::Read;
-use std::error::Error;
+use std::time::Duration;
+use std::str::from_utf8;
 
 use websocket::{Receiver, Sender};
 use websocket::Message as WSMessage;
+use websocket::message::Type;
 use websocket::client::request::Url;
 use websocket::Client;
 
-use serde::json;
+use serde_json as json;
 
 use docopt::Docopt;
 
-use hyper::Client as HClient;
-
-use rustc_serialize::base64::{ToBase64, MIME};
-
-use incoming::Message;
-use ai::{Ai, Action};
+use incoming::{Message, IncomingConnected, Start, IncomingEvents, IncomingEnd, JoinMessage, Action, ActionsMessage};
+use ai::Ai;
+use tyckiting_protocol::automaton::{Automaton, Hooks, Incoming, Step};
+use tyckiting_protocol::strings::{JOIN, ACTIONS};
+use tyckiting_protocol::messages::TIE_TEAM_ID;
+use record::{Recorder, Playback};
 
 static USAGE: &'static str = "
 tyckiting-client - a base for your AI
 
-Usage: tyckiting-client [--host <host>] [--name <name>] [--ai <ai>] [(--port <port> | --webgame <spec>)]
+Usage: tyckiting-client [--host <host>] [--name <name>] [--ai <ai>] [--seed <seed>] [--games <count>] [--record <file>] [(--port <port> | --webgame <spec>)]
+       tyckiting-client [--name <name>] [--ai <ai>] [--seed <seed>] --playback <file>
        tyckiting-client [-h]
 
 
@@ -62,7 +66,11 @@ Options:
   -P, --port <port>         Port to connect to
   -n, --name <name>         Team's name
   -a, --ai <ai>             Select AI
+  -s, --seed <seed>         Seed for the AI's random choices, the same as the recording's to play it back
+  -g, --games <count>       Games to play one after another, 0 to keep playing
   --webgame <spec>          Ask for a game, format user:pass:opponentname
+  -r, --record <file>       Append every message to and from the server to the file
+  --playback <file>         Play the games of a recording again and show what we now send differently
 ";
 
 #[derive(RustcDecodable, Debug)]
@@ -71,20 +79,11 @@ struct Args {
     flag_port: Option<u32>,
     flag_name: Option<String>,
     flag_ai: Option<String>,
-    flag_webgame: Option<String>
-}
-
-#[derive(Serialize)]
-struct WebGameRequest {
-    bots: Vec<String>
-}
-
-#[derive(Deserialize)]
-struct WebGameResponse {
-    #[allow(dead_code)] status: String,
-    #[allow(dead_code)] #[serde(rename="gameId")] game_id: u32,
-    host: String,
-    port: u32
+    flag_seed: Option<usize>,
+    flag_games: Option<u32>,
+    flag_webgame: Option<String>,
+    flag_record: Option<String>,
+    flag_playback: Option<String>
 }
 
 fn main() {
@@ -92,32 +91,38 @@ fn main() {
     let host = args.flag_host.unwrap_or_else(|| "localhost".to_string());
     let team_name = args.flag_name.unwrap_or_else(|| "Team Rust".to_string());
     let ai_name = args.flag_ai.unwrap_or_else(|| "random".to_string());
+    let seed = args.flag_seed.unwrap_or(0);
+
+    if let Some(path) = args.flag_playback {
+        let differences = play_back(Playback::load(&path), &team_name, &ai_name, seed);
+        println!("{} messages differ from the recording", differences);
+        return;
+    }
 
     let (game_host, port) = match args.flag_webgame {
-        Some(webgame) => {
-            let parts: Vec<&str> = webgame.split(":").collect();
-            if parts.len() != 3 {
-                panic!("invalid webgame spec");
-            }
-            let (user, pass, opponent) = (parts[0], parts[1], parts[2]);
-            let auth = format!("{}:{}", user, pass).as_bytes().to_base64(MIME);
-            let mut client = HClient::new();
-            let request_body_string = json::to_string(&WebGameRequest { bots: vec![opponent.to_string()]}).unwrap();
-            let request_body: &str = request_body_string.as_ref();
-            let mut response = client.post(Url::parse(format!("http://{}/api/new", host).as_ref()).unwrap())
-                .header(hyper::header::Authorization(format!("Basic {}", auth)))
-                .body(request_body)
-                .send().unwrap();
-            let mut body = String::new();
-            response.read_to_string(&mut body).unwrap();
-            if !(response.status == hyper::status::StatusCode::Ok) { panic!("Couldn't login"); }
-            let game_response = json::from_str::<WebGameResponse>(body.as_ref()).unwrap_or_else(|e| {panic!("Didn't get proper response, got: {}, error was: {}", body, e)});
-            println!("You can spectate at http://{}:{}.", game_response.host, game_response.port);
-            (game_response.host, game_response.port)
-        },
+        Some(webgame) => webgame::new_game(&host, &webgame),
         None => (host, args.flag_port.unwrap_or_else(|| 3000u32))
     };
 
+    // The server closes the connection when a game ends, so each game gets a new one
+    let games = args.flag_games.unwrap_or(1);
+    let mut recorder = args.flag_record.map(|path| Recorder::create(&path));
+    let (mut played, mut wins) = (0, 0);
+    loop {
+        if play(&game_host, port, &team_name, &ai_name, seed, &mut recorder) {
+            wins += 1;
+        }
+        played += 1;
+        println!("Won {} of {} games", wins, played);
+        if games != 0 && played >= games {
+            break;
+        }
+        thread::sleep(Duration::from_millis(1000));
+    }
+}
+
+// Plays one game, returns whether we won
+fn play(game_host: &str, port: u32, team_name: &str, ai_name: &str, seed: usize, recorder: &mut Option<Recorder>) -> bool {
     let url = Url::parse(format!("ws://{}:{}", game_host, port).as_ref()).unwrap();
 
     let request = Client::connect(url).unwrap();
@@ -125,8 +130,8 @@ fn main() {
     let response_option = request.send();
     let response = match response_option {
         Err(e) => {
-            println!("{:?}", e.cause().unwrap().description());
-            return;
+            println!("{}", e);
+            return false;
         }
         Ok(response) => response
     };
@@ -134,145 +139,184 @@ fn main() {
 
     let (mut sender, mut receiver) = response.begin().split();
 
-    // when _receive_loop goes out of scope, the thread is waited on
-    let _receive_loop = thread::scoped(move || {
-        let mut ai = ai::from_name(ai_name);
-        for message in receiver.incoming_messages() {
-            let message = match message {
-                Ok(m) => m,
-                Err(e) => {
-                    println!("Receive loop error: {:?}", e);
-                    return;
-                }
-            };
-            match message {
-                WSMessage::Close(_) => {
-                    println!("Closing");
-                    return;
-                }
-                WSMessage::Text(msg) => {
-                    let message = incoming::parse_message(msg);
-                    match message {
-                        Message::ConnectedMessage(_) => {
-                            let _ = sender.send_message(WSMessage::Text(json::to_string(& JoinMessage {
-                                type_: "join".to_string(),
-                                team_name: team_name.clone() // clone because compiler doesn't know that this is called just once
-                            }).unwrap()));
-                        },
-                        Message::EndMessage(msg) => {
-                            println!("Thanks for playing!");
-                            match msg.winner_team_id {
-                                Some(winner) => println!("Winner was {}, you are: {}", winner, msg.you.team_id),
-                                None => println!("There was no winner :(")
-                            };
-                            return;
-                        },
-                        Message::EventsMessage(msg) => {
-                            ai.set_state(msg.config, msg.you, msg.other_teams);
-                            let _ = sender.send_message(WSMessage::Text(json::to_string(&to_actionsmessage(
-                                msg.round_id, ai.respond(msg.events))).unwrap()));
-                        },
-                        _ => ()
+    let mut automaton = new_automaton(team_name, ai_name, seed);
+    if let Some(ref mut recorder) = *recorder {
+        recorder.record(record::CONNECT, "");
+    }
+    for message in receiver.incoming_messages() {
+        let message: WSMessage = match message {
+            Ok(m) => m,
+            Err(e) => {
+                println!("Receive loop error: {:?}", e);
+                return false;
+            }
+        };
+        match message.opcode {
+            Type::Close => {
+                println!("Closing");
+                return false;
+            }
+            Type::Text => {
+                let msg = match from_utf8(&message.payload) {
+                    Ok(text) => text.to_string(),
+                    Err(e) => {
+                        println!("Got text that isn't UTF-8: {}", e);
+                        continue;
                     }
+                };
+                match handle_recorded(&mut automaton, msg, recorder) {
+                    Step::Reply(reply) => {
+                        let _ = sender.send_message(&WSMessage::text(reply));
+                    },
+                    Step::Nothing => (),
+                    Step::Ignored(reason) => println!("{}, ignoring", reason),
+                    Step::Finished => return automaton.hooks().won
+                }
+            }
+            _ => println!("Got {:?}. Don't know what to do with it", message)
+        }
+    }
+    false
+}
+
+// Plays the games of a recording with the server's messages from it, returns how many of our
+// messages differ from the recorded ones. Only a game played with the same seed can match.
+fn play_back(mut playback: Playback, team_name: &str, ai_name: &str, seed: usize) -> u32 {
+    while playback.next_connection() {
+        let mut automaton = new_automaton(team_name, ai_name, seed);
+        loop {
+            while let Some(msg) = playback.receive() {
+                match handle_text(&mut automaton, msg) {
+                    Step::Reply(reply) => playback.send(&reply),
+                    Step::Ignored(reason) => println!("{}, ignoring", reason),
+                    _ => ()
                 }
-                _ => println!("Got {:?}. Don't know what to do with it", message)
             }
+            // A new connection in the middle of a game was a reconnect
+            if automaton.is_finished() || !playback.next_connection() {
+                break;
+            }
+            automaton.reconnect();
         }
-    });
+    }
+    playback.differences
 }
 
-#[derive(Debug, Deserialize, Default)]
-pub struct GameConfig {
-    bots: i32,
-    #[serde(rename="fieldRadius")] field_radius: i32,
-    #[serde(rename="move")] move_: u32,
-    #[serde(rename="startHp")] start_hp: i32,
-    cannon: i32,
-    radar: i32,
-    see: i32,
-    #[serde(rename="maxCount")] max_count: i32,
-    #[serde(rename="loopTime")] loop_time: i32
+fn new_automaton(team_name: &str, ai_name: &str, seed: usize) -> Automaton<Player> {
+    let mut automaton = Automaton::new(Player { team_name: team_name.to_string(), ai: ai::from_name(ai_name.to_string(), seed), won: false });
+    automaton.connect();
+    automaton
 }
 
-#[derive(Debug, Deserialize, Serialize, Default)]
-pub struct Position {
-    x: i32,
-    y: i32
+fn handle_text(automaton: &mut Automaton<Player>, msg: String) -> Step<String> {
+    let incoming = match incoming::parse_message(msg) {
+        Message::ConnectedMessage(msg) => Incoming::Connected(msg),
+        Message::StartMessage(msg) => Incoming::Start(msg),
+        Message::EventsMessage(msg) => Incoming::Events(msg),
+        Message::EndMessage(msg) => Incoming::End(msg)
+    };
+    automaton.handle(incoming)
 }
 
-impl Position {
-    #[allow(dead_code)]
-    fn distance(&self, other: Position) -> i32 {
-        // see: http://keekerdc.com/2011/03/hexagon-grids-coordinate-systems-and-distance-calculations/
-        // and http://www.redblobgames.com/grids/hexagons/
-        max((other.x - self.x).abs(),
-            max((other.y - self.y).abs(),
-            ((-other.x - other.y) - (-self.x - self.y)).abs()))
+// Like handle_text, and writes the message and our reply to the recording if there is one
+fn handle_recorded(automaton: &mut Automaton<Player>, msg: String, recorder: &mut Option<Recorder>) -> Step<String> {
+    if let Some(ref mut recorder) = *recorder {
+        recorder.record(record::IN, &msg);
     }
-    #[allow(dead_code)]
-    fn positions_within(&self, distance: u32) -> Vec<Position> {
-        // see http://www.redblobgames.com/grids/hexagons/
-        let dist = distance as i32;
-        let mut ret: Vec<Position> = Vec::new();
-        for dx in -dist..dist + 1 {
-            for dy in (max(-dist, -dx - dist)..min(dist, -dx + dist) + 1) {
-                ret.push(Position { x: self.x + dx, y: self.y + dy});
-            }
+    let step = handle_text(automaton, msg);
+    if let Step::Reply(ref reply) = step {
+        if let Some(ref mut recorder) = *recorder {
+            recorder.record(record::OUT, reply);
         }
-        ret
     }
+    step
 }
 
-#[test]
-fn check_distance() {
-    assert!(Position { x: 0, y: 0}.distance(Position {x: 1, y: 0}) == 1);
-    assert!(Position { x: 0, y: 0}.distance(Position {x: 1, y: 3}) == 4);
-    assert!(Position { x: 0, y: 0}.distance(Position {x: 0, y: 5}) == 5);
+struct Player {
+    team_name: String,
+    ai: Box<Ai>,
+    won: bool
 }
 
-#[test]
-fn check_positions_within() {
-    let pos = Position { x: 0, y: 0};
-    assert!( pos.positions_within(1).len() == 7);
-    assert!( pos.positions_within(2).len() == 19);
-}
+impl Hooks for Player {
+    type Connected = IncomingConnected;
+    type Start = Start;
+    type Events = IncomingEvents;
+    type End = IncomingEnd;
+    type Reply = String;
+
+    fn on_connected(&mut self, _msg: IncomingConnected) -> String {
+        json::to_string(&JoinMessage {
+            event_type: JOIN.to_string(),
+            team_name: self.team_name.clone()
+        }).unwrap()
+    }
 
-#[derive(Debug, Serialize)]
-struct JoinMessage {
-    #[serde(rename="type")] type_: String,
-    #[serde(rename="teamName")] team_name: String
-}
+    fn on_start(&mut self, msg: Start) {
+        self.ai.set_state(msg.config, msg.you, msg.other_teams);
+    }
 
-#[derive(Debug, Serialize)]
-struct SerializableAction {
-    #[serde(rename="type")] type_: String,
-    #[serde(rename="botId")] bot_id: u32,
-    pos: Position
-}
+    fn on_round(&mut self, msg: IncomingEvents) -> String {
+        let (events, errors) = msg.parse_events();
+        for e in errors {
+            println!("Skipping event: {}", e);
+        }
+        self.ai.set_state(msg.config, msg.you, msg.other_teams);
+        json::to_string(&to_actionsmessage(msg.round_id, self.ai.respond(events))).unwrap()
+    }
 
-#[derive(Debug, Serialize)]
-struct ActionsMessage {
-    #[serde(rename="type")] type_: String,
-    #[serde(rename="roundId")] round_id: u32,
-    actions: Vec<SerializableAction>
+    fn on_end(&mut self, msg: IncomingEnd) {
+        println!("Thanks for playing!");
+        self.won = msg.winner_team_id == msg.you.team_id;
+        match msg.winner_team_id {
+            TIE_TEAM_ID => println!("There was no winner :("),
+            winner => println!("Winner was {}, you are: {}", winner, msg.you.team_id)
+        };
+    }
 }
 
 #[allow(dead_code)]
-fn to_actionsmessage(round_id: u32, actions: Vec<Action>) -> ActionsMessage {
-    let serializable_actions = actions.into_iter().map(|action| {
-        match action {
-            Action::RadarAction(a) => SerializableAction {type_: "radar".to_string(), bot_id: a.bot_id, pos: a.pos},
-            Action::MoveAction(a) => SerializableAction {type_: "move".to_string(), bot_id: a.bot_id, pos: a.pos},
-            Action::CannonAction(a) => SerializableAction {type_: "cannon".to_string(), bot_id: a.bot_id, pos: a.pos}
-        }
-    }).collect();
-    ActionsMessage { type_: "actions".to_string(), round_id: round_id, actions: serializable_actions }
+fn to_actionsmessage(round_id: i16, actions: Vec<Action>) -> ActionsMessage {
+    ActionsMessage { event_type: ACTIONS.to_string(), round_id: round_id, actions: actions }
 }
 
 #[test]
 fn test_actionsmessage_serialization() {
-    let generated = json::to_string(&to_actionsmessage(2u32, vec![Action::CannonAction(
-            ai::CannonAction {bot_id: 1u32, pos: Position { x: -1, y: 3 }})])).unwrap();
+    let generated = json::to_string(&to_actionsmessage(2, vec![Action {
+            bot_id: 1, kind: incoming::ActionKind::Cannon, pos: tyckiting_protocol::position::Pos::new(-1, 3) }])).unwrap();
     let wanted = "{\"type\":\"actions\",\"roundId\":2,\"actions\":[{\"type\":\"cannon\",\"botId\":1,\"pos\":{\"x\":-1,\"y\":3}}]}".to_string();
     assert!( generated == wanted);
 }
+
+
+#[test]
+fn test_recording_plays_back_with_the_same_seed() {
+    let config = "{\"bots\":2,\"fieldRadius\":14,\"move\":2,\"startHp\":10,\"cannon\":1,\"radar\":3,\"see\":2,\"maxCount\":200,\"asteroids\":0,\"loopTime\":300,\"noWait\":false}";
+    let you = "{\"name\":\"Team Rust\",\"teamId\":0,\"bots\":[{\"botId\":0,\"name\":\"A\",\"teamId\":0,\"alive\":true,\"pos\":{\"x\":1,\"y\":2},\"hp\":10},{\"botId\":1,\"name\":\"B\",\"teamId\":0,\"alive\":true,\"pos\":{\"x\":-3,\"y\":0},\"hp\":10}]}";
+    let mut messages = vec![format!("{{\"type\":\"connected\",\"teamId\":0,\"config\":{}}}", config),
+                            format!("{{\"type\":\"start\",\"config\":{},\"you\":{},\"otherTeams\":[]}}", config, you)];
+    for round_id in 0..5 {
+        messages.push(format!("{{\"type\":\"events\",\"roundId\":{},\"config\":{},\"you\":{},\"otherTeams\":[],\"events\":[]}}",
+                              round_id, config, you));
+    }
+    messages.push(format!("{{\"type\":\"end\",\"winnerTeamId\":-1,\"you\":{}}}", you));
+
+    let path = std::env::temp_dir().join("tyckiting-client-recording-test.jsonl");
+    let path = path.to_str().unwrap();
+    let _ = std::fs::remove_file(path);
+    {
+        let mut recorder = Some(Recorder::create(path));
+        let mut automaton = new_automaton("Team Rust", "random", 7);
+        recorder.as_mut().unwrap().record(record::CONNECT, "");
+        for msg in messages {
+            handle_recorded(&mut automaton, msg, &mut recorder);
+        }
+        assert!(automaton.is_finished());
+    }
+
+    assert_eq!(play_back(Playback::load(path), "Team Rust", "random", 7), 0);
+    // Another seed answers the rounds differently, the join stays the same
+    assert!(play_back(Playback::load(path), "Team Rust", "random", 8) > 0);
+    assert_eq!(play_back(Playback::load(path), "Team Go", "random", 7), 1);
+    let _ = std::fs::remove_file(path);
+}
@@ -0,0 +1,133 @@
+/** Rust Tyckiting client - A websocket client for a fight to kill all other bots
+ *  Copyright Futurice Oy (2015)
+ *
+ *  This file is part of Rust Tyckiting client.
+ *
+ *  Rust Tyckiting client is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  Rust Tyckiting client is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with Rust Tyckiting client.  If not, see <http://www.gnu.org/licenses/>.
+ */
+// Recording of the messages of a game, in the same JSON lines format as serenity's: one
+// frame per line, every connection starting with a "connect" frame.
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+
+use serde_json::{self as json, Value};
+use time;
+
+pub const IN: &'static str = "in";
+pub const OUT: &'static str = "out";
+pub const CONNECT: &'static str = "connect";
+
+include!(concat!(env!("OUT_DIR"), "/record.rs"));
+
+fn round_of(payload: &str) -> Option<i64> {
+    json::from_str::<Value>(payload).ok()
+        .and_then(|value| value.find("roundId").and_then(|round_id| round_id.as_i64()))
+}
+
+pub struct Recorder {
+    file: File,
+    start: u64
+}
+
+impl Recorder {
+    pub fn create(path: &str) -> Recorder {
+        let file = OpenOptions::new().create(true).append(true).open(path)
+            .unwrap_or_else(|e| panic!("Couldn't open {} for recording: {}", path, e));
+        Recorder { file: file, start: time::precise_time_ns() }
+    }
+
+    pub fn record(&mut self, direction: &str, payload: &str) {
+        let frame = Frame {
+            time: (time::precise_time_ns() - self.start) / 1000000,
+            direction: direction.to_string(),
+            round_id: round_of(payload),
+            payload: payload.to_string()
+        };
+        writeln!(self.file, "{}", json::to_string(&frame).unwrap()).unwrap();
+    }
+}
+
+// Plays the server's side of a recording and compares our replies to the recorded ones
+pub struct Playback {
+    frames: VecDeque<Frame>,
+    pub differences: u32
+}
+
+impl Playback {
+    pub fn load(path: &str) -> Playback {
+        let mut contents = String::new();
+        File::open(path).and_then(|mut f| f.read_to_string(&mut contents))
+            .unwrap_or_else(|e| panic!("Couldn't read recording {}: {}", path, e));
+        Playback::parse(&contents)
+    }
+
+    pub fn parse(contents: &str) -> Playback {
+        let frames = contents.lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| json::from_str::<Frame>(line).unwrap())
+            .collect();
+        Playback { frames: frames, differences: 0 }
+    }
+
+    // Moves on to the next connection, false if there are none left
+    pub fn next_connection(&mut self) -> bool {
+        while let Some(frame) = self.frames.pop_front() {
+            if frame.direction == CONNECT {
+                return true;
+            }
+            self.unanswered(frame);
+        }
+        false
+    }
+
+    // The next message from the server, None when the connection ends
+    pub fn receive(&mut self) -> Option<String> {
+        loop {
+            let direction = match self.frames.front() {
+                Some(frame) => frame.direction.clone(),
+                None => return None
+            };
+            if direction == CONNECT {
+                return None;
+            }
+            let frame = self.frames.pop_front().unwrap();
+            if direction == IN {
+                return Some(frame.payload);
+            }
+            self.unanswered(frame);
+        }
+    }
+
+    pub fn send(&mut self, text: &str) {
+        let recorded = match self.frames.front() {
+            Some(frame) if frame.direction == OUT => Some(frame.payload.clone()),
+            _ => None
+        };
+        if recorded.is_some() {
+            self.frames.pop_front();
+        }
+        if recorded.as_ref().map(|payload| payload.as_ref()) != Some(text) {
+            println!("Round {:?}: recorded {:?}, sent {}", round_of(text), recorded, text);
+            self.differences += 1;
+        }
+    }
+
+    fn unanswered(&mut self, frame: Frame) {
+        if frame.direction == OUT {
+            println!("Round {:?}: recorded {}, sent nothing", frame.round_id, frame.payload);
+            self.differences += 1;
+        }
+    }
+}
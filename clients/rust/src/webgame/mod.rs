@@ -0,0 +1,51 @@
+/** Rust Tyckiting client - A websocket client for a fight to kill all other bots
+ *  Copyright Futurice Oy (2015)
+ *
+ *  This file is part of Rust Tyckiting client.
+ *
+ *  Rust Tyckiting client is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  Rust Tyckiting client is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with Rust Tyckiting client.  If not, see <http://www.gnu.org/licenses/>.
+ */
+// Asking the web frontend for a game against one of its bots
+use std::io::Read;
+
+use hyper::Client;
+use hyper::header::Authorization;
+use hyper::status::StatusCode;
+use rustc_serialize::base64::{ToBase64, MIME};
+use serde_json as json;
+
+include!(concat!(env!("OUT_DIR"), "/webgame.rs"));
+
+// Takes a spec of user:pass:opponentname, returns the host and port of the game server
+pub fn new_game(host: &str, spec: &str) -> (String, u32) {
+    let parts: Vec<&str> = spec.split(":").collect();
+    if parts.len() != 3 {
+        panic!("invalid webgame spec");
+    }
+    let (user, pass, opponent) = (parts[0], parts[1], parts[2]);
+    let auth = format!("{}:{}", user, pass).as_bytes().to_base64(MIME);
+    let client = Client::new();
+    let request_body_string = json::to_string(&WebGameRequest { bots: vec![opponent.to_string()]}).unwrap();
+    let request_body: &str = request_body_string.as_ref();
+    let mut response = client.post(&format!("http://{}/api/new", host))
+        .header(Authorization(format!("Basic {}", auth)))
+        .body(request_body)
+        .send().unwrap();
+    let mut body = String::new();
+    response.read_to_string(&mut body).unwrap();
+    if !(response.status == StatusCode::Ok) { panic!("Couldn't login"); }
+    let game_response = json::from_str::<WebGameResponse>(body.as_ref()).unwrap_or_else(|e| {panic!("Didn't get proper response, got: {}, error was: {}", body, e)});
+    println!("You can spectate at http://{}:{}.", game_response.host, game_response.port);
+    (game_response.host, game_response.port)
+}
@@ -1,4 +1,3 @@
-extern crate syntex;
 extern crate serde_codegen;
 
 use std::env;
@@ -7,16 +6,12 @@ use std::path::Path;
 pub fn main() {
     let out_dir = env::var_os("OUT_DIR").unwrap();
 
-    let src = Path::new("src/defs/mod.rs.in");
-    let dst = Path::new(&out_dir).join("defs.rs");
-    let mut registry = syntex::Registry::new();
-    serde_codegen::register(&mut registry);
-    registry.expand("", &src, &dst).unwrap();
-
     // Copy paste programming FTW!
-    let position_src = Path::new("src/position/mod.rs.in");
-    let position_dst = Path::new(&out_dir).join("position.rs");
-    let mut positionegistry = syntex::Registry::new();
-    serde_codegen::register(&mut positionegistry);
-    positionegistry.expand("", &position_src, &position_dst).unwrap();
+    let replay_src = Path::new("src/replay/mod.rs.in");
+    let replay_dst = Path::new(&out_dir).join("replay.rs");
+    serde_codegen::expand(&replay_src, &replay_dst).unwrap();
+
+    let record_src = Path::new("src/record/mod.rs.in");
+    let record_dst = Path::new(&out_dir).join("record.rs");
+    serde_codegen::expand(&record_src, &record_dst).unwrap();
 }
@@ -0,0 +1,202 @@
+use std::fmt;
+use defs::{Action, ActionKind, Config};
+use position::Pos;
+use lists::{ActionsList, AsteroidList, Decision};
+use ai::Ai;
+
+// What keeping an eye on a target is worth, in expected damage. A cannon straight at a bot
+// that may have moved anywhere in its range does about 0.4, so we'd rather shoot.
+const RADAR_VALUE: f32 = 0.25;
+// What killing a bot is worth on top of the damage it takes, it won't shoot back. This is
+// what makes us go for the wounded ones.
+const KILL_VALUE: f32 = 2.0;
+// Below this an action isn't worth taking, the bot is better left to someone else
+const MIN_GAIN: f32 = 0.05;
+
+// Something we'd like to shoot at
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Target {
+    pub pos: Pos,
+    // How sure we are there's a bot there, 0..1
+    pub confidence: f32,
+    // How much damage it can take, more than this is wasted
+    pub hp: i16,
+}
+
+// One bot's part in the attack, and what we expect to get out of it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Assignment {
+    pub bot_id: i16,
+    pub kind: ActionKind,
+    pub pos: Pos,
+    pub target: Pos,
+    pub gain: f32,
+}
+
+impl fmt::Display for Assignment {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Bot {} {} {} for target {}, expected gain {:.2}", self.bot_id, self.kind, self.pos, self.target, self.gain)
+    }
+}
+
+// The hexes a bot seen at pos could be on once the moves of this round are done
+fn whereabouts<A: AsteroidList>(pos: Pos, config: &Config, asteroids: &A) -> Vec<Pos> {
+    pos.area(config.moves_allowed)
+        .into_iter()
+        .filter(|p| p.distance(Pos::origo()) <= config.field_radius && !asteroids.is_asteroid(*p))
+        .collect()
+}
+
+// The damage a cannon aimed at `aim` does to a bot on `pos`
+pub fn blast(aim: Pos, pos: Pos, cannon: i16) -> i16 {
+    let distance = aim.distance(pos);
+    if distance <= cannon { 1 + cannon - distance } else { 0 }
+}
+
+// Expected damage on a target from cannons aimed at `aims`, with the bot equally likely to be
+// on any hex it could have moved to
+fn expected_damage(target: &Target, places: &Vec<Pos>, aims: &Vec<Pos>, cannon: i16) -> f32 {
+    if places.is_empty() {
+        return 0.0;
+    }
+    let total = places.iter().fold(0.0, |acc, &place| {
+        let damage = aims.iter().fold(0, |acc, &aim| acc + blast(aim, place, cannon));
+        acc + if damage < target.hp { damage as f32 } else { target.hp as f32 + KILL_VALUE }
+    });
+    target.confidence * total / places.len() as f32
+}
+
+// Purpose: split `bots` between `targets`, each bot either firing at a target, radaring it or
+// sitting it out. We hand out actions one at a time, always the one that adds the most expected
+// damage on the enemy minus what we'd do to ourselves (`friends` are where the rest of our
+// bots will be). Any bot can hit any hex, so it doesn't matter which bot gets which action.
+// A target nothing is worth spending a bot on is left alone.
+// Returns the assignments in the order they were made, and the targets we ignored.
+pub fn assign<A: AsteroidList>(targets: &Vec<Target>, bots: &Vec<(i16, Pos)>, friends: &Vec<Pos>,
+                               config: &Config, asteroids: &A) -> (Vec<Assignment>, Vec<Target>) {
+    let places: Vec<Vec<Pos>> = targets.iter().map(|t| whereabouts(t.pos, config, asteroids)).collect();
+    let mut aims: Vec<Vec<Pos>> = targets.iter().map(|_| Vec::new()).collect();
+    let mut radared: Vec<bool> = targets.iter().map(|_| false).collect();
+    let mut free: Vec<(i16, Pos)> = bots.clone();
+    let mut assignments: Vec<Assignment> = Vec::new();
+    // Bots that fire or radar don't move, so the drafted ones can be hit by our blasts too
+    let ours: Vec<Pos> = friends.iter().cloned().chain(bots.iter().map(|&(_, pos)| pos)).collect();
+
+    while !free.is_empty() {
+        // (gain, target index, kind, pos)
+        let mut best: Option<(f32, usize, ActionKind, Pos)> = None;
+        for (t, target) in targets.iter().enumerate() {
+            if !radared[t] {
+                let gain = RADAR_VALUE * target.confidence;
+                if best.map_or(true, |best| gain > best.0) {
+                    best = Some((gain, t, ActionKind::Radar, target.pos));
+                }
+            }
+
+            let before = expected_damage(target, &places[t], &aims[t], config.cannon);
+            for aim in target.pos.area(config.cannon) {
+                if aim.distance(Pos::origo()) > config.field_radius {
+                    continue;
+                }
+                let mut with_aim = aims[t].clone();
+                with_aim.push(aim);
+                let friendly = ours.iter().fold(0, |acc, &friend| acc + blast(aim, friend, config.cannon));
+                let gain = expected_damage(target, &places[t], &with_aim, config.cannon) - before - friendly as f32;
+                if best.map_or(true, |best| gain > best.0) {
+                    best = Some((gain, t, ActionKind::Cannon, aim));
+                }
+            }
+        }
+
+        match best {
+            Some((gain, t, kind, pos)) if gain >= MIN_GAIN => {
+                let (bot_id, _) = free.remove(0);
+                if kind == ActionKind::Radar {
+                    radared[t] = true;
+                } else {
+                    aims[t].push(pos);
+                }
+                assignments.push(Assignment { bot_id: bot_id, kind: kind, pos: pos, target: targets[t].pos, gain: gain });
+            }
+            _ => break,
+        }
+    }
+
+    let ignored = targets.iter()
+        .cloned()
+        .filter(|target| !assignments.iter().any(|a| a.target == target.pos))
+        .collect();
+    (assignments, ignored)
+}
+
+impl Ai {
+    // Share our healthy bots between several targets at once, see assign above
+    pub fn attack_targets(&mut self, actions: &mut Vec<Action>, decision: &mut Decision, targets: &Vec<Target>) {
+        let drafted: Vec<(i16, Pos)> = self.draft_healthy().iter().map(|bot| (bot.id, bot.pos)).collect();
+        // Everyone else stays where they are unless they're already moving away
+        let friends: Vec<Pos> = self.get_live_bots()
+            .iter()
+            .filter(|bot| !drafted.iter().any(|&(id, _)| id == bot.id))
+            .map(|bot| match actions.get_action(bot.id) {
+                Some(action) if action.kind == ActionKind::Move => action.pos,
+                _ => bot.pos,
+            })
+            .collect();
+
+        let (assignments, ignored) = assign(targets, &drafted, &friends, &self.config, &self.asteroids);
+        for assignment in &assignments {
+            actions.set_action_for(assignment.bot_id, assignment.kind, assignment.pos);
+            self.logger.log(&format!("{}", assignment), 2);
+        }
+        for target in &ignored {
+            self.logger.log(&format!("Ignoring target {}, no bot is better spent on it", target.pos), 2);
+        }
+        decision.add_assignment_decision(assignments, ignored.iter().map(|target| target.pos).collect());
+    }
+}
+
+#[cfg(test)]
+fn test_config() -> Config {
+    Config { bots: 3, field_radius: 14, moves_allowed: 2, start_hp: 10, cannon: 1, radar: 3, see: 2, ..Config::default() }
+}
+
+#[test]
+fn assignment_splits_bots_between_targets() {
+    let asteroids: Vec<(Pos, bool)> = Vec::new();
+    let config = test_config();
+    // Both are one hit from dying, so a second cannon on one is worth less than a first on the other
+    let targets = vec![Target { pos: Pos::new(5, 0), confidence: 1.0, hp: 1 },
+                       Target { pos: Pos::new(-5, 0), confidence: 1.0, hp: 1 }];
+    let bots = vec![(0, Pos::new(0, 5)), (1, Pos::new(0, -5)), (2, Pos::new(3, 3))];
+    let (assignments, ignored) = assign(&targets, &bots, &vec![], &config, &asteroids);
+
+    assert!(ignored.is_empty());
+    assert_eq!(assignments.len(), 3);
+    for target in &targets {
+        assert!(assignments.iter().any(|a| a.target == target.pos && a.kind == ActionKind::Cannon));
+    }
+    // Nobody shoots at itself
+    assert!(assignments.iter().all(|a| a.kind != ActionKind::Cannon || a.pos.distance(Pos::new(3, 3)) > 1));
+}
+
+#[test]
+fn assignment_ignores_targets_not_worth_it() {
+    let asteroids: Vec<(Pos, bool)> = Vec::new();
+    let config = test_config();
+    // Too unsure of it to spend a bot on, and any shot would hurt us as well
+    let targets = vec![Target { pos: Pos::new(1, 0), confidence: 0.1, hp: 10 }];
+    let (assignments, ignored) = assign(&targets, &vec![(0, Pos::new(0, 0))], &vec![Pos::new(2, 0)], &config, &asteroids);
+
+    assert_eq!(ignored, targets);
+    assert!(assignments.is_empty());
+}
+
+#[test]
+fn assignment_goes_for_the_wounded() {
+    let asteroids: Vec<(Pos, bool)> = Vec::new();
+    let config = test_config();
+    let targets = vec![Target { pos: Pos::new(5, 0), confidence: 1.0, hp: 10 },
+                       Target { pos: Pos::new(-5, 0), confidence: 1.0, hp: 2 }];
+    let (assignments, _) = assign(&targets, &vec![(0, Pos::new(0, 5))], &vec![], &config, &asteroids);
+    assert_eq!(assignments[0].target, Pos::new(-5, 0));
+}
@@ -0,0 +1,187 @@
+use std::collections::HashMap;
+use std::f32;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::BuildHasherDefault;
+use position::Pos;
+use lists::AsteroidList;
+
+// Something that makes us think there's an asteroid on a hex
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Evidence {
+    // A seeAsteroid event, the server told us
+    Seen,
+    // We shot at an echo while radaring it and hit nothing, sure or not (see is_echo_an_asteroid)
+    EchoWithoutHit,
+    EchoMaybeWithoutHit,
+    // An echo on the exact hex of an echo last round, bots tend to move. Bots that only radar
+    // or fire don't, so these alone never make us sure (see AsteroidMap::add).
+    StaticEcho,
+    // The server dropped a move of ours there that was in range. Only an asteroid does that,
+    // unless the actions it played weren't the ones we think (see validate::rejected_moves).
+    BlockedMove,
+}
+
+impl Evidence {
+    // How much the evidence counts, the thresholds are in the same unit
+    fn weight(self) -> f32 {
+        match self {
+            Evidence::Seen => 1000.0,
+            Evidence::EchoWithoutHit => 1.0,
+            Evidence::EchoMaybeWithoutHit => 0.5,
+            Evidence::StaticEcho => 0.25,
+            Evidence::BlockedMove => 0.5,
+        }
+    }
+}
+
+// How much evidence makes a hex an asteroid, and how much makes it worth keeping an eye on
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Thresholds {
+    pub maybe: f32,
+    pub sure: f32,
+}
+
+impl Default for Thresholds {
+    fn default() -> Thresholds {
+        Thresholds { maybe: 0.25, sure: 1.0 }
+    }
+}
+
+// Hashed the same way on every run, like the heatmap
+type Cells = HashMap<Pos, f32, BuildHasherDefault<DefaultHasher>>;
+
+// Every hex we have some evidence of an asteroid on, and how much
+pub struct AsteroidMap {
+    cells: Cells,
+    thresholds: Thresholds,
+    // How many asteroids the server put on the field, config.asteroids
+    expected: i16,
+}
+
+impl AsteroidMap {
+    pub fn new(expected: i16) -> AsteroidMap {
+        AsteroidMap::with_thresholds(expected, Thresholds::default())
+    }
+
+    pub fn with_thresholds(expected: i16, thresholds: Thresholds) -> AsteroidMap {
+        AsteroidMap { cells: Cells::default(), thresholds: thresholds, expected: expected }
+    }
+
+    pub fn evidence(&self, pos: Pos) -> f32 {
+        *self.cells.get(&pos).unwrap_or(&0.0)
+    }
+
+    // Returns true if this made us sure of the asteroid
+    pub fn add(&mut self, pos: Pos, evidence: Evidence) -> bool {
+        let was_sure = self.is_asteroid(pos);
+        let limit = match evidence {
+            Evidence::StaticEcho => self.thresholds.sure - evidence.weight(),
+            _ => f32::INFINITY,
+        };
+        {
+            let cell = self.cells.entry(pos).or_insert(0.0);
+            if *cell < limit {
+                *cell = (*cell + evidence.weight()).min(limit);
+            }
+        }
+        !was_sure && self.is_asteroid(pos)
+    }
+
+    // There's a bot on the hex, so whatever we thought was wrong
+    pub fn clear(&mut self, pos: Pos) {
+        if self.evidence(pos) < Evidence::Seen.weight() {
+            self.cells.remove(&pos);
+        }
+    }
+
+    // The asteroids we're sure of, in a stable order
+    pub fn found(&self) -> Vec<Pos> {
+        let mut found: Vec<Pos> = self.cells.keys().cloned().filter(|pos| self.is_asteroid(*pos)).collect();
+        found.sort_by_key(|pos| (pos.x, pos.y));
+        found
+    }
+
+    // Once we know where every asteroid is, there's no need to look for more
+    pub fn all_found(&self) -> bool {
+        self.found().len() as i16 >= self.expected
+    }
+}
+
+impl AsteroidList for AsteroidMap {
+    fn register(&mut self, pos: Pos) {
+        self.add(pos, Evidence::Seen);
+    }
+
+    fn register_maybe(&mut self, pos: Pos) {
+        self.add(pos, Evidence::EchoMaybeWithoutHit);
+    }
+
+    fn is_asteroid(&self, pos: Pos) -> bool {
+        self.evidence(pos) >= self.thresholds.sure
+    }
+
+    fn is_maybe_asteroid(&self, pos: Pos) -> bool {
+        self.evidence(pos) >= self.thresholds.maybe
+    }
+}
+
+#[test]
+fn asteroid_map_adds_up_evidence() {
+    let mut map = AsteroidMap::new(2);
+    assert!(!map.add(Pos::new(1, 1), Evidence::EchoMaybeWithoutHit));
+    assert!(map.is_maybe_asteroid(Pos::new(1, 1)) && !map.is_asteroid(Pos::new(1, 1)));
+    assert!(map.add(Pos::new(1, 1), Evidence::EchoMaybeWithoutHit));
+
+    // A bot turned up where we thought a static echo was an asteroid
+    map.add(Pos::new(3, 0), Evidence::StaticEcho);
+    map.clear(Pos::new(3, 0));
+    assert!(!map.is_maybe_asteroid(Pos::new(3, 0)));
+
+    // However long it keeps still, a static echo could be a bot, until we shoot at it
+    for _ in 0..10 {
+        assert!(!map.add(Pos::new(3, 0), Evidence::StaticEcho));
+    }
+    assert!(map.is_maybe_asteroid(Pos::new(3, 0)) && !map.is_asteroid(Pos::new(3, 0)));
+    assert!(map.add(Pos::new(3, 0), Evidence::EchoMaybeWithoutHit));
+    map.clear(Pos::new(3, 0));
+
+    assert!(!map.all_found());
+    map.register(Pos::new(-2, 4));
+    map.clear(Pos::new(-2, 4));
+    assert_eq!(map.found(), vec![Pos::new(-2, 4), Pos::new(1, 1)]);
+    assert!(map.all_found());
+}
+
+#[test]
+fn static_echoes_we_hit_are_bots() {
+    use defs::{Action, ActionKind, EchoEvent, Event, HitEvent};
+    use lists::HistoryList;
+    use log::Logger;
+    use sim::{default_config, Simulator};
+    use ai::Ai;
+
+    let config = ::defs::Config { asteroids: 2, ..default_config() };
+    let sim = Simulator::new(config, &vec!["Serenity".to_string(), "Reavers".to_string()], 1);
+    let mut ai = Ai::new(&sim.start_message(0), Logger::disabled(), 1);
+    let (shot, ignored) = (Pos::new(6, -2), Pos::new(-6, 3));
+
+    // Two bots that never move echo every round. We shoot at one of them and hit it every time.
+    let mut kept = Vec::new();
+    for round_id in 0..6 {
+        ai.round_id = round_id;
+        let mut events = vec![Event::Echo(EchoEvent { pos: shot }), Event::Echo(EchoEvent { pos: ignored })];
+        if round_id > 0 {
+            events.push(Event::Hit(HitEvent { bot_id: 3, source: 0 }));
+        }
+        let events = ai.filter_asteroids_from_events(&events);
+        kept = events.clone();
+        ai.history.add_events(round_id, &events);
+        ai.history.add_actions(round_id, &vec![Action { bot_id: 0, kind: ActionKind::Cannon, pos: shot }]);
+    }
+
+    assert_eq!(ai.asteroids.evidence(shot), 0.0);
+    // The other one could be an asteroid, but we keep its echoes so it still gets shot at
+    assert!(ai.asteroids.is_maybe_asteroid(ignored) && !ai.asteroids.is_asteroid(ignored));
+    assert!(kept.iter().any(|ev| ev.pos() == Some(ignored)));
+    assert!(!ai.asteroids.all_found());
+}
@@ -1,9 +1,9 @@
-use defs::{ Action, Event, DieEvent };
-use strings::{ CANNON, RADAR, MOVE, HIT, SEE, RADARECHO, DIE };
+use defs::{ Action, ActionKind, Event, EventKind, DieEvent };
 use position::Pos;
 use patterns::*;
 use ai::*;
 use ai::bot::Bot;
+use ai::assign::Target;
 use lists::*;
 use lists::ActionMode::*;
 use std::cmp::max;
@@ -38,26 +38,39 @@ impl Ai {
     }
 
     fn shoot_echoes_exit_code(&mut self, mut actions: &mut Vec<Action>, decision: &mut Decision) -> Option<bool> {
-        // Are there any echoes this round? shoot at them...
-        let see_positions_this_round = self.history.get_echo_positions(1)
+        // Did we see any of the bots we're tracking this round? shoot at them...
+        // The tracks we've been following the longest come first
+        let tracks_this_round = self.live_tracks()
+            .into_iter()
+            .filter(|track| track.last_seen == self.round_id)
+            .collect::<Vec<_>>();
+        let see_positions_this_round = tracks_this_round
             .iter()
-            .map(|tup| tup.0)
+            .map(|track| track.pos)
             .collect::<Vec<_>>();
 
         println!("See positions this round {:?}", see_positions_this_round);
-        if see_positions_this_round.len() > 0 {
-            //TODO: Handle multiple seen ones
-            println!("Radar position found this round {:?}", see_positions_this_round[0]);
+        if tracks_this_round.len() > 1 {
+            let targets = tracks_this_round
+                .iter()
+                .map(|track| Target { pos: track.pos, confidence: track.confidence, hp: self.estimated_hp(track.bot_id) })
+                .collect();
+            self.attack_targets(&mut actions, decision, &targets);
+            self.log_attack_actions(&actions, "have fresh seen data on several targets");
+            return Some(true);
+        }
+        if let Some(target) = tracks_this_round.first() {
+            println!("Radar position found this round {:?}, track {}", target.pos, target.id);
 
-            //TODO: Don't do this if we've already found all asteroids
             // Because of asteroids we want to make sure that the first time we see something
-            // We scan as we shoot so we can mark detect asteroids
-            if self.last_action_mode() == Scan {
-                self.attack_and_scan_pos(&mut actions, see_positions_this_round[0]);
+            // We scan as we shoot so we can mark detect asteroids, unless we've found them all
+            let new_target = self.last_action_mode() == Scan || target.length == 1;
+            if new_target && !self.asteroids.all_found() {
+                self.attack_and_scan_pos(&mut actions, target.pos);
             } else {
-                self.attack_pos(&mut actions, see_positions_this_round[0]);
+                self.attack_pos(&mut actions, target.pos);
             }
-            decision.add_attack_decision(&see_positions_this_round[0], &see_positions_this_round);
+            decision.add_attack_decision(&target.pos, &see_positions_this_round);
 
             self.log_attack_actions(&actions, "have fresh seen data");
             return Some(true);
@@ -97,18 +110,19 @@ impl Ai {
 
     fn see_lastround_exit_code(&mut self, mut actions: &mut Vec<Action>, decision: &mut Decision) -> Option<bool> {
         // Since we got here we know we have no echoes or hits this round,
-        // how about last round?
-            let see_positions = self.history.get_echo_positions(2);
-            let see_positions_last_round = see_positions.iter()
-                .filter(|tup|tup.1 == self.last_round())
-                .filter_map(|tup| if !self.asteroids.is_asteroid(tup.0) { Some(tup.0) } else { None })
-                .collect::<Vec<_>>();
-
-            if see_positions_last_round.len() > 0 {
-                println!("Radar position found last round {:?}", see_positions_last_round[0]);
-                decision.add_attack_decision(&see_positions_last_round[0], &see_positions_last_round);
-                self.attack_and_scan_pos(&mut actions, see_positions_last_round[0]);
-                self.log_attack_actions(&actions, "have one round old seen data");
+        // how about tracks we last saw last round?
+        let see_positions_last_round = self.live_tracks()
+            .iter()
+            .filter(|track| track.last_seen == self.last_round())
+            .map(|track| track.pos)
+            .collect::<Vec<_>>();
+
+        if see_positions_last_round.len() > 0 {
+            println!("Radar position found last round {:?}", see_positions_last_round[0]);
+            let aim = self.aim_after_a_move(see_positions_last_round[0]);
+            decision.add_attack_decision(&aim, &see_positions_last_round);
+            self.attack_and_scan_pos(&mut actions, aim);
+            self.log_attack_actions(&actions, "have one round old seen data");
             return Some(true);
         }
         return None;
@@ -122,7 +136,7 @@ impl Ai {
             .filter_map(|ref ev|self.get_pos_from_hit(ev, self.last_round()))
             .collect::<Vec<Pos>>();
         if hit_positions_last_round.len() > 0 {
-            let pos = hit_positions_last_round[0];
+            let pos = self.aim_after_a_move(hit_positions_last_round[0]);
             println!("Pos of last round hit {:?}", pos);
             decision.add_attack_decision(&pos, &hit_positions_last_round);
             self.attack_and_scan_pos(&mut actions, pos);
@@ -132,8 +146,20 @@ impl Ai {
         return None;
     }
 
+    // A bot we knew the position of last round has had a round to move since. The heatmap
+    // followed it there, so aim at where it expects the most of it near the old position.
+    fn aim_after_a_move(&mut self, last_known: Pos) -> Pos {
+        match self.heatmap.best_target_near(last_known, self.config.moves_allowed, self.config.cannon) {
+            Some((aim, mass)) if mass > 0.0 => {
+                self.logger.log(&format!("Heatmap moves the aim from {} to {}, {:.2} enemies expected there.", last_known, aim, mass), 2);
+                aim
+            }
+            _ => last_known,
+        }
+    }
+
     fn hits_on_enemies(&self, since: i16) -> Vec<(Event, i16)> {
-        self.history.get_events( HIT, since )
+        self.history.get_events( EventKind::Hit, since )
             .iter()
             .cloned()
             .filter(|tup|{
@@ -206,12 +232,13 @@ impl Ai {
         let available_bots = self.draft_healthy();
         let bots_alive = available_bots.len() as i16;
 
+        let spread = smart_attack_spread(target, bots_alive, self.config.field_radius, &mut self.rng);
         available_bots.iter()
-            .zip(smart_attack_spread(target, bots_alive, self.config.field_radius)
+            .zip(spread
                 .iter()
                 .map(|pos| self.avoid_friendly_fire(pos)
             ))
-            .map(|(&ref bot, ref pos)| actions.set_action_for(bot.id, CANNON, pos.clamp(&radius)))
+            .map(|(&ref bot, ref pos)| actions.set_action_for(bot.id, ActionKind::Cannon, pos.clamp(&radius)))
             .count();
     }
 
@@ -225,7 +252,7 @@ impl Ai {
             .iter()
             .cloned()
             .filter(|bot| {
-                actions.get_action(bot.id).unwrap().action_type != MOVE.to_string()
+                actions.get_action(bot.id).unwrap().kind != ActionKind::Move
             })
             .collect::<Vec<_>>();
 
@@ -234,7 +261,7 @@ impl Ai {
         // and the following will be attack positions
         let available_bot_count = available_bots.len();
         let mut positions: Vec<Pos> = vec![target];
-        positions.append(&mut smart_attack_spread(target, available_bot_count as i16, self.config.field_radius));
+        positions.append(&mut smart_attack_spread(target, available_bot_count as i16, self.config.field_radius, &mut self.rng));
         let mut radared = false;
 
         println!("Attacking or scanning with {:?} bots, to: {:?}", available_bot_count, positions);
@@ -244,16 +271,17 @@ impl Ai {
             .zip(positions)
             .map(|(&ref bot, ref pos)| {
                 if !radared {
-                    actions.set_action_for(bot.id, RADAR, *pos);
+                    actions.set_action_for(bot.id, ActionKind::Radar, *pos);
                     radared = true;
                 } else {
-                    actions.set_action_for(bot.id, CANNON, self.avoid_friendly_fire(pos));
+                    // The spread can reach past the edge of the field
+                    actions.set_action_for(bot.id, ActionKind::Cannon, self.avoid_friendly_fire(pos).clamp(&self.config.field_radius));
                 }
             })
             .count();
     }
 
-    fn draft_healthy(&self) -> Vec<Bot> {
+    pub fn draft_healthy(&self) -> Vec<Bot> {
         self.get_live_bots().into_iter()
             .filter(|bot| bot.is_healthy())
             .collect()
@@ -265,8 +293,8 @@ impl Ai {
     #[allow(dead_code)]
     fn find_echo_within_radius(&self, target: Pos, max_radius: i16) -> Option<(Pos,i16)> {
         // get relevant events 10 rounds back
-        let mut see_events = self.history.get_events( SEE, 10 );
-        see_events.append(&mut self.history.get_events( RADARECHO, 10 ));
+        let mut see_events = self.history.get_events( EventKind::See, 10 );
+        see_events.append(&mut self.history.get_events( EventKind::Echo, 10 ));
 
         see_events
             .iter()
@@ -295,7 +323,7 @@ impl Ai {
             .iter()
             .filter(|bot| bot.alive && {
                 if let Some(ac) = actions.iter().find(|ac| ac.bot_id == bot.id) {
-                    ac.action_type != MOVE.to_string()
+                    ac.kind != ActionKind::Move
                 } else {
                     false
                 }
@@ -309,6 +337,10 @@ impl Ai {
     // with the enemy bot position, but can be anywhere within the cannon radius (1)
     pub fn get_pos_from_hit(&self, hit_event: &Event, round_id: i16) -> Option<Pos> {
         let previous_round: i16 = round_id - 1;
+        // We didn't play that round, e.g. we've just resumed the game
+        if previous_round < 0 || previous_round >= self.history.len() as i16 {
+            return None;
+        }
         let mut source: i16 = -1;
         match hit_event {
             &Event::Hit(ref ev) => source = ev.source,
@@ -316,13 +348,13 @@ impl Ai {
         };
         println!("get_pos_from_hit_entry previous round {:?} source {}", previous_round, source);
         let cannons = self.history
-            .get_actions_for_round( CANNON, previous_round )
+            .get_actions_for_round( ActionKind::Cannon, previous_round )
             .iter()
             .cloned()
             .filter(|ac| ac.bot_id == source)
             .collect::<Vec<Action>>();
             println!("Cannon matches {:?}", cannons);
-            println!("Cannon actions {:?}", self.history.get_actions_for_round( CANNON, previous_round ));
+            println!("Cannon actions {:?}", self.history.get_actions_for_round( ActionKind::Cannon, previous_round ));
         // Should be guaranteed to have 1 cannon match
         if cannons.len() > 0 {
             return Some(cannons[0].pos);
@@ -334,7 +366,7 @@ impl Ai {
     // Returns one die event on enemy bot from this round is there was one. Else None.
     #[allow(dead_code)]
     fn get_possible_kill(&self) -> Option<DieEvent> {
-        for entry in self.history.get_events( DIE, 1 ) {
+        for entry in self.history.get_events( EventKind::Die, 1 ) {
             match entry.0 {
                 Event::Die(ref ev) => {
                     if !self.is_our_bot(ev.bot_id) {
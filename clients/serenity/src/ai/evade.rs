@@ -1,101 +1,73 @@
-use defs::{ Action, Event };
-use strings::{DETECTED, DAMAGED, MOVE};
+use defs::{ Action, ActionKind };
 use position::Pos;
-use rand;
-use rand::Rng;
 use ai::*;
 use lists::*;
 use ai::bot::Bot;
+use ai::threat::EVADE_THRESHOLD;
 
 impl Ai {
-    #[allow(dead_code)]
-    pub fn evade_action(&self, bot: &Bot) -> Action {
-        if self.bots_alive() >= 2 {
-            return self.evade_spread(bot);
-        } else {
-            return self.evade_random(bot);
-        }
-    }
-
-    pub fn evade_pos(&self, bot: &Bot) -> Pos {
-        if self.bots_alive() >= 2 {
-            return self.evade_spread_pos(bot);
-        } else {
-            return self.evade_random_pos(bot);
-        }
-    }
-
+    // Purpose: get every bot the enemy is likely to shoot at next round out of the way, to the
+    // least threatened hex it can reach, or towards a safe hex when it can't reach one this
+    // round. The most threatened bots pick first.
     pub fn evade_if_needed(&mut self, actions: &mut Vec<Action>) {
-        // Let's evade if we were seen in the last two turns
-        let mut evade_events = self.history.get_events( DETECTED, 1 );
-        evade_events.append(&mut self.history.get_events( DAMAGED, 1 ));
+        let threats = self.threat_map();
+        let mut threatened: Vec<(f32, Bot)> = self.get_live_bots()
+            .into_iter()
+            .map(|bot| (threats.get(bot.pos), bot))
+            .filter(|&(threat, _)| threat >= EVADE_THRESHOLD)
+            .collect();
+        threatened.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap());
 
-        for ev in evade_events {
-            match ev.0 {
-                Event::Detected(ref ev) => {
-                    let destination = self.evade_pos(self.get_bot(ev.bot_id).unwrap());
-                    actions.set_action_for(ev.bot_id, MOVE, destination);
-                    self.logger.log(&format!("Evading Bot {} to {}", ev.bot_id, destination), 2);
-                }
-                Event::Damaged(ref ev) => {
-                    let destination = self.evade_pos(self.get_bot(ev.bot_id).unwrap());
-                    actions.set_action_for(ev.bot_id, MOVE, destination);
-                    self.logger.log(&format!("Evading Bot {} to {}", ev.bot_id, destination), 2);
+        for (threat, bot) in threatened {
+            let friends: Vec<Pos> = self.get_live_bots()
+                .iter()
+                .filter(|other| other.id != bot.id)
+                .map(|other| match actions.get_action(other.id) {
+                    Some(action) if action.kind == ActionKind::Move => action.pos,
+                    _ => other.pos,
+                })
+                .collect();
+            let mut destination = threats.safest(bot.pos, self.config.moves_allowed, self.config.field_radius,
+                                                 &friends, &self.asteroids);
+            if threats.get(destination) >= EVADE_THRESHOLD {
+                // Nothing we can reach this round is safe, so start on the way to the closest
+                // hex that is, through as little of the blast as we can
+                let safe: Vec<Pos> = bot.pos.area(self.config.cannon + 1 + self.config.moves_allowed)
+                    .into_iter()
+                    .filter(|pos| threats.get(*pos) < EVADE_THRESHOLD)
+                    .collect();
+                if let Some(route) = self.route_for(bot.id, &safe) {
+                    destination = route[0];
                 }
-                _ => ()
             }
+            if destination == bot.pos {
+                continue;
+            }
+            actions.set_action_for(bot.id, ActionKind::Move, destination);
+            self.logger.log(&format!("Evading Bot {} to {}, threat {:.2} -> {:.2}",
+                                     bot.id, destination, threat, threats.get(destination)), 2);
         }
     }
+}
 
-    #[allow(dead_code)]
-    fn evade_random(&self, bot: &Bot) -> Action {
-        let move_to = self.evade_random_pos(&bot);
-        println!("MOVES: {}, {}, {}, {}", bot.pos.x, bot.pos.y, move_to.x, move_to.y);
-        return Action {
-            bot_id: bot.id,
-            action_type: MOVE.to_string(),
-            pos: move_to,
-        };
-    }
-
-    fn evade_random_pos(&self, bot: &Bot) -> Pos {
-        let neighbors = bot.pos.clamped_neighbors(self.config.moves_allowed, self.config.field_radius)
-            .into_iter()
-            .filter(|&pos| !self.asteroids.is_asteroid(pos))
-            .collect::<Vec<Pos>>();
-        *rand::thread_rng()
-            .choose(&neighbors)
-            .expect("Oh there were no neighbors? That's impossible.")
-    }
-
-    #[allow(dead_code)]
-    fn evade_spread(&self, bot: &Bot) -> Action {
-        let move_to = self.evade_spread_pos(&bot);
-
-        return Action {
-            bot_id: bot.id,
-            action_type: MOVE.to_string(),
-            pos: move_to.clone(),
-        };
-    }
+#[test]
+fn evade_heads_out_of_a_blast_it_cant_leave_in_one_move() {
+    use defs::{DetectedEvent, Event};
+    use log::Logger;
+    use sim::{default_config, Simulator};
 
-    fn evade_spread_pos(&self, bot: &Bot) -> Pos {
-        // When too close to the edge of the board the spread logic doesn't move enough
-        if bot.pos.distance(Pos{x: 0, y: 0}) > &self.config.field_radius - 2 {
-            return self.evade_random_pos(&bot);
-        }
-        let neighbors = bot.pos.clamped_neighbors(self.config.moves_allowed, self.config.field_radius);
-        let otherbots: Vec<&Bot> = self.bots.iter()
-            .by_ref()
-            .filter(|otherbot| otherbot.id != bot.id)
-            .collect();
+    // The blast and its spread reach two hexes out, and we move one hex a round
+    let config = ::defs::Config { moves_allowed: 1, cannon: 1, ..default_config() };
+    let sim = Simulator::new(config, &vec!["Serenity".to_string(), "Reavers".to_string()], 1);
+    let mut ai = Ai::new(&sim.start_message(0), Logger::disabled(), 1);
+    ai.history.add_events(0, &vec![Event::Detected(DetectedEvent { bot_id: 0 })]);
+    let threats = ai.threat_map();
+    let from = ai.get_bot(0).unwrap().pos;
+    assert!(from.area(1).iter().all(|pos| threats.get(*pos) >= EVADE_THRESHOLD));
 
-        neighbors.into_iter()
-            .filter(|&pos| !self.asteroids.is_asteroid(pos))
-            .max_by_key( |pos| otherbots.iter()
-                .map(|otherbot| pos.distance(otherbot.pos))
-                .min()
-                .expect("There should be other bots"))
-            .expect("There should be neighbor positions")
-    }
+    let mut actions = <Vec<Action> as ActionsList>::populate(&ai.bots);
+    ai.evade_if_needed(&mut actions);
+    let action = actions.get_action(0).unwrap();
+    assert_eq!(action.kind, ActionKind::Move);
+    assert_eq!(action.pos.distance(from), 1);
 }
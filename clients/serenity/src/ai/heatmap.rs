@@ -0,0 +1,276 @@
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::BuildHasherDefault;
+use defs::{ActionKind, Event};
+use defs::Event::*;
+use position::Pos;
+use lists::{AsteroidList, HistoryList};
+use ai::Ai;
+use util;
+
+// Hashed the same way on every run, so the cells are visited in the same order and sums and
+// ties come out the same. The default hasher is seeded randomly.
+type Cells = HashMap<Pos, f32, BuildHasherDefault<DefaultHasher>>;
+
+// Below this we consider a hex empty
+const EPSILON: f32 = 0.0001;
+
+// Our belief of where the enemy bots are. Every hex holds the expected number of enemy
+// bots on it, so the whole map sums up to the number of enemies alive.
+pub struct Heatmap {
+    cells: Cells,
+}
+
+impl Heatmap {
+    // We know nothing at the start, every hex is equally likely
+    pub fn new(field_radius: i16, enemies: usize) -> Heatmap {
+        let positions = Pos::origo().area(field_radius);
+        let mass = enemies as f32 / positions.len() as f32;
+        Heatmap {
+            cells: positions.into_iter().map(|pos| (pos, mass)).collect(),
+        }
+    }
+
+    pub fn get(&self, pos: Pos) -> f32 {
+        *self.cells.get(&pos).unwrap_or(&0.0)
+    }
+
+    pub fn total(&self) -> f32 {
+        self.cells.values().fold(0.0, |acc, &mass| acc + mass)
+    }
+
+    // The enemy had a round to move, so every hex spreads its mass evenly over the hexes
+    // a bot could have reached from it (staying put included). Asteroids can't be entered.
+    pub fn diffuse<A: AsteroidList>(&mut self, moves_allowed: i16, asteroids: &A) {
+        let mut result: Cells = self.cells.keys().map(|&pos| (pos, 0.0)).collect();
+
+        for (&pos, &mass) in &self.cells {
+            if mass < EPSILON {
+                continue;
+            }
+            let reachable: Vec<Pos> = pos.area(moves_allowed)
+                .into_iter()
+                .filter(|p| self.cells.contains_key(p) && !asteroids.is_asteroid(*p))
+                .collect();
+            let share = mass / reachable.len() as f32;
+            for p in reachable {
+                *result.get_mut(&p).unwrap() += share;
+            }
+        }
+
+        self.cells = result;
+    }
+
+    // Asteroid hexes never hold a bot
+    pub fn clear_asteroids<A: AsteroidList>(&mut self, asteroids: &A) {
+        for (pos, mass) in self.cells.iter_mut() {
+            if asteroids.is_asteroid(*pos) {
+                *mass = 0.0;
+            }
+        }
+    }
+
+    // A radar (or a cannon that didn't hit) covered this area and found nothing
+    pub fn observe_empty(&mut self, center: Pos, radius: i16) {
+        for pos in center.area(radius) {
+            if let Some(mass) = self.cells.get_mut(&pos) {
+                *mass = 0.0;
+            }
+        }
+    }
+
+    // There's a bot on this exact hex
+    pub fn observe_bot(&mut self, pos: Pos) {
+        if let Some(mass) = self.cells.get_mut(&pos) {
+            *mass = 1.0;
+        }
+    }
+
+    // One of our cannons hit a bot somewhere in this area, but we don't know where
+    pub fn observe_hit(&mut self, center: Pos, radius: i16) {
+        let area: Vec<Pos> = center.area(radius)
+            .into_iter()
+            .filter(|pos| self.cells.contains_key(pos))
+            .collect();
+        if area.is_empty() {
+            return;
+        }
+        let area_mass = area.iter().fold(0.0, |acc, pos| acc + self.get(*pos));
+
+        if area_mass < EPSILON {
+            let share = 1.0 / area.len() as f32;
+            for pos in area {
+                self.cells.insert(pos, share);
+            }
+        } else if area_mass < 1.0 {
+            let scale = 1.0 / area_mass;
+            for pos in area {
+                *self.cells.get_mut(&pos).unwrap() *= scale;
+            }
+        }
+    }
+
+    // Scales the map to sum up to the number of enemies alive. Hexes in `fixed` are ones we
+    // just saw a bot on, those keep their mass and the rest of the map shares what's left.
+    pub fn normalize<A: AsteroidList>(&mut self, enemies: usize, fixed: &Vec<Pos>, asteroids: &A) {
+        let fixed_mass = fixed.iter().fold(0.0, |acc, pos| acc + self.get(*pos));
+        let left = (enemies as f32 - fixed_mass).max(0.0);
+        let free_mass = self.total() - fixed_mass;
+
+        if free_mass < EPSILON {
+            // Our information doesn't add up (it happens, echoes from unknown asteroids etc.)
+            // so spread the rest evenly over everything that could hold a bot
+            let free: Vec<Pos> = self.cells.keys()
+                .cloned()
+                .filter(|pos| !fixed.contains(pos) && !asteroids.is_asteroid(*pos))
+                .collect();
+            let share = if free.len() > 0 { left / free.len() as f32 } else { 0.0 };
+            for pos in free {
+                self.cells.insert(pos, share);
+            }
+        } else {
+            let scale = left / free_mass;
+            for (pos, mass) in self.cells.iter_mut() {
+                if !fixed.contains(pos) {
+                    *mass *= scale;
+                }
+            }
+        }
+    }
+
+    // The single most likely hex
+    #[allow(dead_code)]
+    pub fn hottest(&self) -> Option<(Pos, f32)> {
+        self.cells.iter()
+            .map(|(&pos, &mass)| (pos, mass))
+            .fold(None, |acc, curr| match acc {
+                Some(best) => if curr.1 > best.1 { Some(curr) } else { Some(best) },
+                None => Some(curr),
+            })
+    }
+
+    // The hex to aim at if we want to cover the most enemies within radius,
+    // e.g. config.radar for scanning or config.cannon for shooting.
+    // Returns the position and expected number of enemies covered.
+    pub fn best_target(&self, radius: i16) -> Option<(Pos, f32)> {
+        self.cells.keys()
+            .map(|&pos| (pos, self.mass_within(pos, radius)))
+            .fold(None, |acc, curr| match acc {
+                Some(best) => if curr.1 > best.1 { Some(curr) } else { Some(best) },
+                None => Some(curr),
+            })
+    }
+
+    // Like best_target, but only aiming within reach of center, e.g. at where a bot we knew
+    // to be on center could have moved since. The closest hex to center wins a tie.
+    pub fn best_target_near(&self, center: Pos, reach: i16, radius: i16) -> Option<(Pos, f32)> {
+        center.area(reach)
+            .into_iter()
+            .filter(|pos| self.cells.contains_key(pos))
+            .map(|pos| (pos, self.mass_within(pos, radius)))
+            .fold(None, |acc, curr| match acc {
+                Some(best) => {
+                    let closer = curr.0.distance(center) < best.0.distance(center);
+                    if curr.1 > best.1 || (curr.1 == best.1 && closer) { Some(curr) } else { Some(best) }
+                }
+                None => Some(curr),
+            })
+    }
+
+    fn mass_within(&self, center: Pos, radius: i16) -> f32 {
+        center.area(radius)
+            .iter()
+            .fold(0.0, |acc, p| acc + self.get(*p))
+    }
+}
+
+impl Ai {
+    // Purpose: move our belief of the enemy positions forward one round. The enemy may have
+    // moved, and then our radars and cannons from last round tell us where they are, and where
+    // they are not.
+    pub fn update_heatmap(&mut self, events: &Vec<Event>, enemies: usize) {
+        self.heatmap.diffuse(self.config.moves_allowed, &self.asteroids);
+
+        let mut echoes: Vec<Pos> = Vec::new();
+        let mut hit_sources: Vec<i16> = Vec::new();
+        for event in events {
+            match *event {
+                Echo(ref ev) => echoes.push(ev.pos),
+                See(ref ev) => echoes.push(ev.pos),
+                Hit(ref ev) => {
+                    if !self.is_our_bot(ev.bot_id) {
+                        hit_sources.push(ev.source);
+                    }
+                }
+                _ => (),
+            }
+        }
+        util::dedup_nosort(&mut echoes);
+
+        // Actions we sent last round are the ones the server resolved this round
+        if self.round_id > 0 {
+            let last_round = self.round_id - 1;
+            for action in self.history.get_actions_for_round( ActionKind::Cannon, last_round) {
+                if hit_sources.contains(&action.bot_id) {
+                    self.heatmap.observe_hit(action.pos, self.config.cannon);
+                } else {
+                    self.heatmap.observe_empty(action.pos, self.config.cannon);
+                }
+            }
+            for action in self.history.get_actions_for_round( ActionKind::Radar, last_round) {
+                self.heatmap.observe_empty(action.pos, self.config.radar);
+            }
+        }
+
+        for &pos in &echoes {
+            self.heatmap.observe_bot(pos);
+        }
+        self.heatmap.clear_asteroids(&self.asteroids);
+        self.heatmap.normalize(enemies, &echoes, &self.asteroids);
+
+        if let Some((pos, mass)) = self.heatmap.best_target(self.config.radar) {
+            self.logger.log(&format!("Heatmap: {:.2} enemies expected within radar range of {}", mass, pos), 2);
+        }
+    }
+}
+
+#[test]
+fn heatmap_keeps_enemy_count() {
+    let asteroids: Vec<(Pos, bool)> = vec![(Pos::new(1, 1), true)];
+    let mut map = Heatmap::new(14, 3);
+    map.observe_empty(Pos::origo(), 3);
+    map.diffuse(2, &asteroids);
+    map.clear_asteroids(&asteroids);
+    map.normalize(3, &vec![], &asteroids);
+    assert!((map.total() - 3.0).abs() < 0.01);
+    assert_eq!(map.get(Pos::new(1, 1)), 0.0);
+    assert!(map.get(Pos::origo()) < map.get(Pos::new(10, 0)));
+}
+
+#[test]
+fn heatmap_finds_echo() {
+    let mut map = Heatmap::new(14, 1);
+    map.observe_empty(Pos::new(3, 3), 3);
+    map.observe_bot(Pos::new(4, 3));
+    map.normalize(1, &vec![Pos::new(4, 3)], &Vec::<(Pos, bool)>::new());
+    assert_eq!(map.hottest().unwrap().0, Pos::new(4, 3));
+    assert!(map.total() < 1.01);
+}
+
+#[test]
+fn heatmap_follows_a_bot_that_moved() {
+    let nothing = Vec::<(Pos, bool)>::new();
+    let mut map = Heatmap::new(14, 1);
+    map.observe_bot(Pos::origo());
+    map.normalize(1, &vec![Pos::origo()], &nothing);
+
+    // A round later it could be anywhere two hexes away, but our radar found nothing west of it
+    map.diffuse(2, &nothing);
+    map.observe_empty(Pos::new(-3, 0), 3);
+    map.normalize(1, &vec![], &nothing);
+
+    let (aim, mass) = map.best_target_near(Pos::origo(), 2, 1).unwrap();
+    assert!(aim.x > 0);
+    assert!(mass > map.mass_within(Pos::origo(), 1));
+    assert!(map.best_target_near(Pos::new(-10, 0), 2, 1).unwrap().1 < 0.001);
+}
@@ -2,30 +2,56 @@ extern crate serde; extern crate serde_json;
 
 use defs;
 use defs::Event::*;
-use defs::{Config, Event, Action, ActionsMessage, IncomingEvents };
+use defs::{Config, Event, EventKind, Action, ActionKind, ActionsMessage, IncomingEvents };
 use tribool::Tribool;
 use tribool::Tribool::*;
 use position::Pos;
-use strings::{ ACTIONS, NOACTION, CANNON };
+use strings::ACTIONS;
 use lists::*;
 use lists::ActionMode::*;
 use ai::bot::Bot;
+use ai::heatmap::Heatmap;
+use ai::track::Tracker;
+use ai::opponents::Opponents;
+use ai::asteroids::{AsteroidMap, Evidence};
+use ai::strategy::Composer;
+use ai::validate::Report;
 use log::Logger;
+use rand::StdRng;
+use util;
+use std::mem;
 
 mod radar;
 mod evade;
 mod scan;
 mod attack;
+mod heatmap;
+mod track;
+mod opponents;
+mod vision;
+mod threat;
+mod asteroids;
+mod path;
+pub mod assign;
+pub mod validate;
 pub mod bot;
+pub mod strategy;
 
 pub struct Ai {
     bots: Vec<Bot>,
     round_id: i16,
     radar_positions: (i16, Vec<Pos>),
     history: Vec<HistoryEntry>,
-    asteroids: Vec<(Pos, bool)>,
+    asteroids: AsteroidMap,
+    heatmap: Heatmap,
+    tracks: Tracker,
+    opponents: Opponents,
+    strategies: Composer,
     config: Config,
     logger: Logger,
+    violations: Vec<(i16, Report)>,
+    // Every random choice we make comes from here
+    rng: StdRng,
 }
 
 impl Ai {
@@ -36,17 +62,11 @@ impl Ai {
         self.logger.log("Decisions", 1);
         println!("\n---------------------------\nROUND: {:?}\n---------------------------\n", self.round_id);
 
-        // Let each bot evade as needed
-        self.evade_if_needed(&mut actions);
+        // Let the strategies claim bots in turn, see strategy.rs for the defaults
+        let mut strategies = mem::replace(&mut self.strategies, Composer::new());
+        strategies.run(self, &mut actions, &mut decision);
+        self.strategies = strategies;
 
-        // Attack if we have a target, evading bots will continue evading
-        self.aggressive_attack_strategy(&mut actions, &mut decision);
-
-        // If not attacking, use non evading bots to scan in a sequence
-        match decision.mode {
-            Attack => (),
-            _ => self.scan_with_idle_bots(&mut actions, &mut decision),
-        }
         self.logger.log(&format!("Mode: {}", decision.mode), 2);
 
         println!("Action are {:?}", actions);
@@ -54,7 +74,8 @@ impl Ai {
         return (decision, actions);
     }
 
-    pub fn new(start: &defs::Start) -> Ai {
+    // Teams seeded alike still get their own numbers
+    pub fn new(start: &defs::Start, logger: Logger, seed: usize) -> Ai {
         // TODO: separate into smaller functions to do set up
         let mut radar: radar::Radar = radar::Radar::new();
         let radar_positions = &radar.get_radar_positions(&start.config);
@@ -64,13 +85,24 @@ impl Ai {
             round_id: -1,
             radar_positions: (0, radar_positions.clone()),
             history: Vec::new(),
-            asteroids: Vec::new(),
+            asteroids: AsteroidMap::new(start.config.asteroids),
+            heatmap: Heatmap::new(start.config.field_radius, count_live_enemies(&start.other_teams)),
+            tracks: Tracker::new(),
+            opponents: Opponents::new(&start.other_teams, start.config.start_hp),
+            strategies: Composer::default(),
             config: start.config.clone(),
-            logger: Logger::new(),
+            logger: logger,
+            violations: Vec::new(),
+            rng: util::seeded_rng(&[seed, start.you.team_id as usize]),
         };
     }
 
 
+    pub fn set_strategies(&mut self, strategies: Composer) {
+        self.logger.log(&format!("Strategies: {}", strategies.names().join(", ")), 0);
+        self.strategies = strategies;
+    }
+
     // TODO: This does not actually need to be mutable
     fn make_actions_message(&self, mut actions: Vec<Action>) -> ActionsMessage {
         actions.reverse();  // Apparently by "latest", futurice means "first in array". So we need
@@ -100,27 +132,12 @@ impl Ai {
         if self.asteroids.is_asteroid(pos) {
             return Yes;
         }
+        // No more asteroids to find, so it's a bot
+        if self.asteroids.all_found() {
+            return No;
+        }
 
-        let we_sure;
-        let cannon_positions = match self.bots_alive() {
-            1 => {
-                // If we only have one bot, we couldn't shoot and fire at once.
-                we_sure = Maybe;
-                self.history
-                    .get_actions_for_round( CANNON, self.round_id - 2 )
-                    .iter()
-                    .map(|ac|ac.pos)
-                    .collect()
-            }
-            _ => {
-                we_sure = Yes;
-                self.history
-                    .get_actions_for_round( CANNON, self.round_id - 1 )
-                    .iter()
-                    .map(|ac|ac.pos)
-                    .collect()
-            }
-        };
+        let (cannon_positions, we_sure) = self.cannons_at_echoes();
 
         // First we check if the position matches any cannon actions
         // Otherwise we can't draw any conclusions
@@ -138,6 +155,26 @@ impl Ai {
         }
     }
 
+    // Where we shot while radaring the echoes of this round, and how sure we can be that the
+    // hits we got are from those shots
+    fn cannons_at_echoes(&self) -> (Vec<Pos>, Tribool) {
+        let (we_sure, round_id) = match self.bots_alive() {
+            // If we only have one bot, we couldn't shoot and fire at once.
+            1 => (Maybe, self.round_id - 2),
+            _ => (Yes, self.round_id - 1),
+        };
+        // Nothing to go by at the start of the game, or of the part of it we've been in
+        if round_id < 0 || round_id >= self.history.len() as i16 {
+            return (Vec::new(), we_sure);
+        }
+        let cannon_positions = self.history
+            .get_actions_for_round( ActionKind::Cannon, round_id )
+            .iter()
+            .map(|ac|ac.pos)
+            .collect();
+        (cannon_positions, we_sure)
+    }
+
     fn is_in_hit_events(&self, pos: Pos, hit_events: &Vec<Event>) -> bool {
         return hit_events
             .iter()
@@ -158,27 +195,58 @@ impl Ai {
             })
             .collect::<Vec<_>>();
 
+        // A see event shows a bot, so whatever we had on that hex wasn't an asteroid
+        for event in events {
+            if let See(ref ev) = *event {
+                self.asteroids.clear(ev.pos);
+            }
+        }
+
+        let last_round = self.round_id - 1;
+        let echoes_last_round: Vec<Pos> = if last_round >= 0 && last_round < self.history.len() as i16 {
+            self.history.get_events_for_round( EventKind::Echo, last_round )
+                .iter()
+                .filter_map(|ev| ev.pos())
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        let (cannon_positions, _) = self.cannons_at_echoes();
+
         let mut events: Vec<Event> = events.to_vec();
         events.retain(|event| { match *event {
             Echo(ref ev) => {
-                let is_asteroid_now = self.is_echo_an_asteroid(ev.pos, &hit_events_this_round );
+                if self.asteroids.is_asteroid(ev.pos) {
+                    return false;
+                }
 
-                if Yes == is_asteroid_now && !self.asteroids.is_asteroid(ev.pos) {
-                    self.logger.log(&format!("Recorded an asteroid at {}.", ev.pos), 2);
-                    self.asteroids.register(ev.pos);
-                } else if Maybe == is_asteroid_now {
-                    if self.asteroids.is_maybe_asteroid(ev.pos) {
-                        println!("Two maybes on {:?}, it's probbly an asteroid.", ev.pos);
-                        self.logger.log(&format!("Recorded an asteroid at {} because of 2 maybes.", ev.pos), 2);
-                        self.asteroids.register(ev.pos);
+                // We hit something there, a bot was on it or close enough
+                let hit = self.is_in_hit_events(ev.pos, &hit_events_this_round);
+                if hit {
+                    self.asteroids.clear(ev.pos);
+                }
+                // A bot that stands still echoes on the same hex too, so that only counts
+                // when we didn't shoot at it and see whether it was hit
+                let shot_at = self.is_pos_in_cannon_actions(ev.pos, &cannon_positions);
+
+                let evidence = match self.is_echo_an_asteroid(ev.pos, &hit_events_this_round ) {
+                    Yes => Some(Evidence::EchoWithoutHit),
+                    Maybe => Some(Evidence::EchoMaybeWithoutHit),
+                    No if !hit && !shot_at && !self.asteroids.all_found() && echoes_last_round.contains(&ev.pos) =>
+                        Some(Evidence::StaticEcho),
+                    No => None,
+                };
+                if let Some(evidence) = evidence {
+                    if self.asteroids.add(ev.pos, evidence) {
+                        self.logger.log(&format!("Recorded an asteroid at {} ({:?}).", ev.pos, evidence), 2);
                     } else {
-                        println!("Echo {:?} might be an asteroid", ev.pos);
-                        self.logger.log(&format!("Recording that there might be an asteroid at {}.", ev.pos), 2);
-                        self.asteroids.register_maybe(ev.pos);
+                        self.logger.log(&format!("Recording that there might be an asteroid at {} ({:?}), evidence {:.2}.",
+                                                 ev.pos, evidence, self.asteroids.evidence(ev.pos)), 2);
                     }
                 }
 
-                Yes != is_asteroid_now
+                !self.asteroids.is_asteroid(ev.pos)
             }
             _ => true
         }});
@@ -231,26 +299,30 @@ impl Ai {
                     //TODO!!!
                     log.push((format!("Echo enemy at {}", ev.pos), 2));
                 }
-                Damaged(ref ev) => {
-                    let mut bot = self.get_bot_mut(ev.bot_id).expect("No bot on our team with this id wtf?");
-                    bot.hp -= ev.damage;
-                    log.push((format!("Bot {} damaged {} hp, {} hp left.", ev.bot_id, ev.damage, bot.hp), 2));
-                }
-                Move(ref ev) => {
-                    let mut bot = self.get_bot_mut(ev.bot_id).expect("No bot on our team with this id wtf?");
-                    let oldpos = bot.pos;
-                    bot.pos = ev.pos;
-                    log.push((format!("Move own bot {} from {} to {}", bot.id, oldpos, bot.pos), 2));
-                }
-                Detected(ref ev) => {
-                    let bot = self.get_bot(ev.bot_id).expect("No bot on our team with this id wtf?");
-                    log.push((format!("Was Detected own bot {} on pos {}", bot.id, bot.pos), 2));
-                }
+                // These are only ever about our own bots, the server got something wrong if not
+                Damaged(ref ev) => match self.get_bot_mut(ev.bot_id) {
+                    Some(bot) => {
+                        bot.hp -= ev.damage;
+                        log.push((format!("Bot {} damaged {} hp, {} hp left.", ev.bot_id, ev.damage, bot.hp), 2));
+                    }
+                    None => log.push((format!("Skipping damaged event, bot {} is not on our team", ev.bot_id), 1)),
+                },
+                Move(ref ev) => match self.get_bot_mut(ev.bot_id) {
+                    Some(bot) => {
+                        let oldpos = bot.pos;
+                        bot.pos = ev.pos;
+                        log.push((format!("Move own bot {} from {} to {}", bot.id, oldpos, bot.pos), 2));
+                    }
+                    None => log.push((format!("Skipping move event, bot {} is not on our team", ev.bot_id), 1)),
+                },
+                Detected(ref ev) => match self.get_bot(ev.bot_id) {
+                    Some(bot) => log.push((format!("Was Detected own bot {} on pos {}", bot.id, bot.pos), 2)),
+                    None => log.push((format!("Skipping detected event, bot {} is not on our team", ev.bot_id), 1)),
+                },
                 Noaction(_) => {
                     //TODO: Maybe we can use the knowledge that a bot is sleeping? To exploit bugs
                     //in enemy code ;)
                 }
-                _ => {}
             }
         }
 
@@ -261,16 +333,26 @@ impl Ai {
         self.round_id = events_json.round_id;
         self.logger.log(&format!("round {}", self.round_id), 0);
 
-        let events = events_json.events.iter().map(defs::parse_event).collect();
+        // A malformed event is skipped, we'd rather act on the rest than miss the round
+        let (events, errors) = events_json.parse_events();
+        for e in errors {
+            println!("Skipping event: {}", e);
+            self.logger.log(&format!("Skipped malformed event: {}", e), 1);
+        }
+        self.check_moves(&events);
         let events: Vec<Event> = self.filter_asteroids_from_events(&events);
         self.update_state(&events);
 
         // Add events to history after filtering out asteroids
         self.history.add_events(self.round_id, &events);
+        self.update_heatmap(&events, count_live_enemies(&events_json.other_teams));
+        self.update_tracks(&events);
+        self.update_opponents(&events, &events_json.other_teams);
 
         // Get mode and actions for the round and add those to history too
         let (decision, mut actions) = self.make_decisions();
-        actions.retain(|ref ac| ac.action_type != NOACTION.to_string());
+        actions.retain(|ref ac| ac.kind != ActionKind::Noaction);
+        let actions = self.validate_actions(&actions);
 
         self.history.add_actions(self.round_id, &actions);
         self.history.set_decision(self.round_id, decision);
@@ -308,3 +390,36 @@ impl Ai {
 fn gen_bots(start: &defs::Start) -> Vec<Bot> {
     return start.you.bots.iter().map(Bot::new).collect();
 }
+
+// The server lists every enemy bot under each of the other teams, so count unique ids
+fn count_live_enemies(other_teams: &Vec<defs::Team>) -> usize {
+    let mut ids: Vec<i16> = other_teams.iter()
+        .flat_map(|team| team.bots.iter())
+        .filter(|bot| bot.alive)
+        .map(|bot| bot.bot_id)
+        .collect();
+    ids.sort();
+    ids.dedup();
+    ids.len()
+}
+
+#[test]
+fn events_about_bots_we_dont_have_are_skipped() {
+    use defs::{DamagedEvent, DetectedEvent, MaybeEvent, MoveEvent};
+    use log::Logger;
+    use sim::{default_config, Simulator};
+
+    let mut sim = Simulator::new(default_config(), &vec!["Serenity".to_string(), "Reavers".to_string()], 1);
+    let mut ai = Ai::new(&sim.start_message(0), Logger::disabled(), 1);
+    let mut msg = sim.play_round(&vec![]).into_iter().find(|&(team_id, _)| team_id == 0).unwrap().1;
+    let before: Vec<(i16, i16, Pos)> = ai.bots.iter().map(|bot| (bot.id, bot.hp, bot.pos)).collect();
+
+    // Bot 3 is one of theirs
+    msg.events = vec![Damaged(DamagedEvent { bot_id: 3, damage: 2 }),
+                      Move(MoveEvent { bot_id: 3, pos: Pos::new(1, 1) }),
+                      Detected(DetectedEvent { bot_id: 3 })].into_iter().map(MaybeEvent::from).collect();
+    let actions = ai.handle_message(msg);
+
+    assert!(!actions.actions.is_empty());
+    assert_eq!(ai.bots.iter().map(|bot| (bot.id, bot.hp, bot.pos)).collect::<Vec<_>>(), before);
+}
@@ -0,0 +1,167 @@
+use defs::{Action, ActionKind, Event, Team};
+use defs::Event::*;
+use position::Pos;
+use lists::HistoryList;
+use ai::Ai;
+use ai::assign::blast;
+use std::cmp::Ordering::Equal;
+
+// What we believe about one enemy bot
+#[derive(Debug, Clone, PartialEq)]
+pub struct Enemy {
+    pub id: i16,
+    pub team_id: i16,
+    pub name: String,
+    pub alive: bool,
+    // Never less than it really has, we only count damage we're sure of
+    pub hp: i16,
+    // Where and in which round we last had it pinned down
+    pub last_seen: Option<(Pos, i16)>,
+}
+
+pub struct Opponents {
+    enemies: Vec<Enemy>,
+}
+
+impl Opponents {
+    // The server lists every enemy bot under each of the other teams, so go by bot id
+    pub fn new(other_teams: &Vec<Team>, start_hp: i16) -> Opponents {
+        let mut enemies: Vec<Enemy> = Vec::new();
+        for bot in other_teams.iter().flat_map(|team| team.bots.iter()) {
+            if enemies.iter().any(|enemy| enemy.id == bot.bot_id) {
+                continue;
+            }
+            enemies.push(Enemy {
+                id: bot.bot_id,
+                team_id: bot.team_id,
+                name: bot.name.clone(),
+                alive: bot.alive,
+                hp: if bot.alive { bot.hp.unwrap_or(start_hp) } else { 0 },
+                last_seen: None,
+            });
+        }
+        Opponents { enemies: enemies }
+    }
+
+    pub fn get(&self, id: i16) -> Option<&Enemy> {
+        self.enemies.iter().find(|enemy| enemy.id == id)
+    }
+
+    // Enemies still in the game, the most wounded first
+    pub fn alive(&self) -> Vec<Enemy> {
+        let mut alive: Vec<Enemy> = self.enemies.iter().filter(|enemy| enemy.alive).cloned().collect();
+        alive.sort_by(|a, b| {
+            match a.hp.cmp(&b.hp) {
+                Equal => a.id.cmp(&b.id),
+                order => order,
+            }
+        });
+        alive
+    }
+
+    // Purpose: bring the model up to date with this round's events. `cannons` are the cannon
+    // actions we sent last round, the ones that caused this round's hits. A hit costs the bot
+    // 1 + cannon - distance, which we only know when a see event tells us where it was.
+    // Otherwise we count the least a hit can do.
+    pub fn update(&mut self, round_id: i16, events: &Vec<Event>, cannons: &Vec<Action>, cannon: i16) {
+        for event in events {
+            if let See(ref ev) = *event {
+                if let Some(enemy) = self.enemies.iter_mut().find(|enemy| enemy.id == ev.bot_id) {
+                    enemy.last_seen = Some((ev.pos, round_id));
+                }
+            }
+        }
+
+        for event in events {
+            match *event {
+                Hit(ref ev) => {
+                    let aim = cannons.iter()
+                        .find(|ac| ac.bot_id == ev.source && ac.kind == ActionKind::Cannon)
+                        .map(|ac| ac.pos);
+                    if let Some(enemy) = self.enemies.iter_mut().find(|enemy| enemy.id == ev.bot_id) {
+                        let damage = match (aim, enemy.last_seen) {
+                            (Some(aim), Some((pos, seen))) if seen == round_id => blast(aim, pos, cannon),
+                            _ => 0,
+                        };
+                        let damage = if damage > 0 { damage } else { 1 };
+                        // It's alive until we get a die event
+                        enemy.hp = if enemy.hp - damage > 1 { enemy.hp - damage } else { 1 };
+                    }
+                }
+                Die(ref ev) => {
+                    if let Some(enemy) = self.enemies.iter_mut().find(|enemy| enemy.id == ev.bot_id) {
+                        enemy.alive = false;
+                        enemy.hp = 0;
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+
+    // The server tells us who's alive every round, trust that over our own bookkeeping
+    pub fn sync_alive(&mut self, other_teams: &Vec<Team>) {
+        for bot in other_teams.iter().flat_map(|team| team.bots.iter()) {
+            if !bot.alive {
+                if let Some(enemy) = self.enemies.iter_mut().find(|enemy| enemy.id == bot.bot_id) {
+                    enemy.alive = false;
+                    enemy.hp = 0;
+                }
+            }
+        }
+    }
+}
+
+impl Ai {
+    pub fn update_opponents(&mut self, events: &Vec<Event>, other_teams: &Vec<Team>) {
+        // Actions we sent last round are the ones the server resolved this round
+        let cannons = if self.round_id > 0 {
+            self.history.get_actions_for_round( ActionKind::Cannon, self.round_id - 1 )
+        } else {
+            Vec::new()
+        };
+        self.opponents.update(self.round_id, events, &cannons, self.config.cannon);
+        self.opponents.sync_alive(other_teams);
+
+        for enemy in self.opponents.alive() {
+            self.logger.log(&format!("Enemy bot {} ({}) has at most {} hp, last seen {:?}",
+                                     enemy.id, enemy.name, enemy.hp, enemy.last_seen), 2);
+        }
+    }
+
+    // How much hp the bot at a track can have left, as far as we know
+    pub fn estimated_hp(&self, bot_id: Option<i16>) -> i16 {
+        bot_id.and_then(|id| self.opponents.get(id))
+            .map(|enemy| enemy.hp)
+            .unwrap_or(self.config.start_hp)
+    }
+}
+
+#[cfg(test)]
+fn enemy_team() -> Vec<Team> {
+    let bot = |id| ::defs::Bot { bot_id: id, name: format!("Reaver {}", id), team_id: 0, alive: true, pos: None, hp: None };
+    let team = Team { name: "Reavers".to_string(), team_id: 0, bots: vec![bot(0), bot(1)] };
+    // Listed twice, like the server does with more than one other team
+    vec![team.clone(), team]
+}
+
+#[test]
+fn opponents_count_damage_from_hits() {
+    let mut opponents = Opponents::new(&enemy_team(), 10);
+    assert_eq!(opponents.alive().len(), 2);
+
+    let cannons = vec![Action { bot_id: 5, kind: ActionKind::Cannon, pos: Pos::new(3, 3) },
+                       Action { bot_id: 6, kind: ActionKind::Cannon, pos: Pos::new(-3, 0) }];
+    let events = vec![Hit(::defs::HitEvent { bot_id: 0, source: 5 }),
+                      See(::defs::SeeEvent { source: 5, bot_id: 0, pos: Pos::new(3, 3) }),
+                      Hit(::defs::HitEvent { bot_id: 1, source: 6 })];
+    opponents.update(4, &events, &cannons, 1);
+
+    // A direct hit we saw, and one we only know happened
+    assert_eq!(opponents.get(0).map(|enemy| (enemy.hp, enemy.last_seen)), Some((8, Some((Pos::new(3, 3), 4)))));
+    assert_eq!(opponents.get(1).map(|enemy| enemy.hp), Some(9));
+    assert_eq!(opponents.alive()[0].id, 0);
+
+    opponents.update(5, &vec![Die(::defs::DieEvent { bot_id: 1 })], &vec![], 1);
+    assert_eq!(opponents.alive().iter().map(|enemy| enemy.id).collect::<Vec<_>>(), vec![0]);
+}
@@ -0,0 +1,111 @@
+use std::collections::HashMap;
+use position::Pos;
+use lists::AsteroidList;
+use ai::Ai;
+
+// How many rounds of walking a certain blast is worth avoiding
+const THREAT_WEIGHT: f32 = 2.0;
+
+// Purpose: plan the moves that get a bot from `from` onto any hex of `goal` in as few rounds
+// as possible. Every round the bot can land on any free hex within moves_allowed, the server
+// lets it jump over whatever is in between. Landing on a hex costs a round plus its threat,
+// so we'll walk around dangerous hexes when it doesn't take too long.
+// Returns where to move each round, or None if no goal hex can be reached.
+pub fn plan_route<B, T>(from: Pos, goal: &Vec<Pos>, moves_allowed: i16, field_radius: i16, blocked: B, threat: T)
+    -> Option<Vec<Pos>>
+    where B: Fn(Pos) -> bool, T: Fn(Pos) -> f32
+{
+    let goal: Vec<Pos> = goal.iter()
+        .cloned()
+        .filter(|pos| pos.is_on_field(field_radius) && !blocked(*pos))
+        .collect();
+    if goal.is_empty() || moves_allowed < 1 {
+        return None;
+    }
+
+    // The fewest rounds it could take, never more than it really does
+    let rounds_left = |pos: Pos| {
+        let distance = goal.iter().map(|g| g.distance(pos)).min().unwrap();
+        ((distance + moves_allowed - 1) / moves_allowed) as f32
+    };
+
+    // (hex, cost so far) of hexes we've reached but not yet looked around from
+    let mut open: Vec<(Pos, f32)> = vec![(from, 0.0)];
+    let mut cost: HashMap<Pos, f32> = HashMap::new();
+    let mut came_from: HashMap<Pos, Pos> = HashMap::new();
+    cost.insert(from, 0.0);
+
+    while !open.is_empty() {
+        let index = (0..open.len())
+            .fold(0, |best, i| {
+                if open[i].1 + rounds_left(open[i].0) < open[best].1 + rounds_left(open[best].0) { i } else { best }
+            });
+        let (pos, so_far) = open.remove(index);
+
+        if goal.contains(&pos) {
+            let mut route = vec![pos];
+            while let Some(&previous) = came_from.get(route.last().unwrap()) {
+                route.push(previous);
+            }
+            route.pop();
+            route.reverse();
+            return Some(route);
+        }
+
+        for next in pos.area(moves_allowed) {
+            if next == pos || !next.is_on_field(field_radius) || blocked(next) {
+                continue;
+            }
+            let next_cost = so_far + 1.0 + THREAT_WEIGHT * threat(next);
+            if cost.get(&next).map_or(false, |&known| known <= next_cost) {
+                continue;
+            }
+            cost.insert(next, next_cost);
+            came_from.insert(next, pos);
+            open.retain(|&(p, _)| p != next);
+            open.push((next, next_cost));
+        }
+    }
+    None
+}
+
+impl Ai {
+    // Where to move a bot each round to get it onto one of the goal hexes, around known
+    // asteroids and the hexes we expect the enemy to shoot at next round
+    pub fn route_for(&self, bot_id: i16, goal: &Vec<Pos>) -> Option<Vec<Pos>> {
+        let bot = match self.get_bot(bot_id) {
+            Some(bot) => bot,
+            None => return None,
+        };
+        let threats = self.threat_map();
+        plan_route(bot.pos, goal, self.config.moves_allowed, self.config.field_radius,
+                   |pos| self.asteroids.is_asteroid(pos), |pos| threats.get(pos))
+    }
+}
+
+#[test]
+fn route_goes_around_asteroids_and_threats() {
+    let asteroids: Vec<(Pos, bool)> = vec![(Pos::new(2, 0), true)];
+    let no_threat = |_: Pos| 0.0;
+
+    // Two rounds of two hexes, but the only hex halfway there is an asteroid
+    let route = plan_route(Pos::origo(), &vec![Pos::new(4, 0)], 2, 14, |_| false, &no_threat).unwrap();
+    assert_eq!(route, vec![Pos::new(2, 0), Pos::new(4, 0)]);
+    let route = plan_route(Pos::origo(), &vec![Pos::new(4, 0)], 2, 14, |pos| asteroids.is_asteroid(pos), &no_threat).unwrap();
+    assert_eq!(route.len(), 3);
+    assert!(!route.contains(&Pos::new(2, 0)) && route[2] == Pos::new(4, 0));
+
+    // Any hex of a corner of the field will do
+    let corner: Vec<Pos> = Pos::new(10, -10).area(2);
+    let route = plan_route(Pos::origo(), &corner, 2, 14, |pos| asteroids.is_asteroid(pos), &no_threat).unwrap();
+    assert_eq!(route.len(), 4);
+    assert!(corner.contains(route.last().unwrap()));
+
+    // A longer way is better than a likely hit
+    let threat = |pos: Pos| if pos.distance(Pos::new(1, 0)) <= 1 { 0.9 } else { 0.0 };
+    let route = plan_route(Pos::origo(), &vec![Pos::new(3, 0)], 2, 14, |_| false, &threat).unwrap();
+    assert!(route.iter().all(|pos| threat(*pos) == 0.0));
+
+    // Nowhere to go
+    assert_eq!(plan_route(Pos::origo(), &vec![Pos::new(2, 0)], 2, 14, |pos| asteroids.is_asteroid(pos), &no_threat), None);
+}
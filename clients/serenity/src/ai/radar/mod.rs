@@ -1,117 +1,432 @@
+use std::collections::{HashMap, HashSet};
+use rand::{Rng, SeedableRng, StdRng};
 use position::Pos;
 use defs::{Config};
 
+// Radar plans are built from perfect tilings of the plane with hexagons of the radar radius.
+// A hexagon of radius r holds 3r^2 + 3r + 1 hexes, and placing their centres on the lattice
+// spanned by (2r + 1, -r) and (r, r + 1) (or its mirror image) covers every hex exactly once.
+// We try every offset of both lattices, keep the tiles that touch the field, move the centres
+// that fall outside the field back in (the server ignores radars outside the field) and pick
+// the plan with the fewest centres. The tiles on the edge of the field are mostly wasted
+// though, so shrink then searches for a cover with fewer centres. For field radius 14 that
+// brings radar 3 from 24 centres down to 22 and radar 2 from 43 to 42. That may still not be
+// minimal: no cover can do with fewer than 18 and 34 (see lower_bound), and the search can't
+// tell whether one between those exists.
+
+// How long shrink looks for a cover with one centre less, and how long a centre it moved stays
+const SEARCH_MOVES: usize = 1500;
+const STILL_MOVES: usize = 3;
+
 pub struct Radar {
     pub radar_positions: Vec<Pos>,
-    pub meta: RadarMeta
-}
-
-#[derive(Clone, Copy, Debug)]
-pub struct RadarMeta {
     field_radius: i16,
     radar_radius: i16,
-    row_start: i16,
-    row_end: i16,
-    row_start_change: i16,
-    row_end_change: i16,
-}
-
-pub struct RadarPass {
-    last_pos: Option<Pos>,
-    meta: RadarMeta
-}
-
-impl RadarMeta {
-    pub fn new(field_radius: i16, radar_radius: i16) -> RadarMeta {
-        RadarMeta {
-            field_radius: field_radius,
-            radar_radius: radar_radius,
-            row_start: 0,
-            row_end: field_radius - radar_radius,
-            row_start_change: -(radar_radius + 1),
-            row_end_change: 0
-        }
-    }
-
-    pub fn with_defaults() -> RadarMeta {
-        RadarMeta::new(14,2)
-    }
 }
 
 impl Radar {
     pub fn new() -> Radar {
         Radar {
             radar_positions: Vec::new(),
-            meta: RadarMeta::with_defaults(),
+            field_radius: 0,
+            radar_radius: 0,
         }
     }
 
     pub fn get_radar_positions(&mut self, config: &Config) -> Vec<Pos> {
         // If we already have calculated the radar positions for this config return it
-        if self.meta.field_radius == config.field_radius &&
-            self.meta.radar_radius == config.radar &&
+        if self.field_radius == config.field_radius &&
+            self.radar_radius == config.radar &&
             self.radar_positions.len() > 0
         {
             return self.radar_positions.clone();
         }
 
-        // Reset meta for new calculation
-        self.meta = RadarMeta::new(config.field_radius, config.radar);
-
-        // Start in the top left corner
-        let start_pos = Pos {
-            x: self.meta.row_start,
-            y: -(config.field_radius - config.radar)
-        };
-        let last_pos = start_pos;
-        self.radar_positions.push(start_pos);
-
-        let meta = self.meta.clone();
-        let mut pass: RadarPass = RadarPass{
-            last_pos: Some(last_pos),
-            meta: meta
-        };
-        loop {
-            pass = get_next_position(pass);
-            if pass.last_pos.is_some() {
-                self.radar_positions.push(pass.last_pos.unwrap());
-            } else {
+        self.field_radius = config.field_radius;
+        self.radar_radius = config.radar;
+        self.radar_positions = order_by_discovery(&plan_cover(config.field_radius, config.radar), config.field_radius, config.radar);
+        return self.radar_positions.clone();
+    }
+}
+
+// Number of hexes within radius of a position, itself included
+pub fn area_size(radius: i16) -> usize {
+    let r = radius as usize;
+    3 * r * r + 3 * r + 1
+}
+
+// No complete cover of the field can have fewer centres than this, a plan this long is minimal
+#[allow(dead_code)]
+pub fn lower_bound(field_radius: i16, radar_radius: i16) -> usize {
+    let field = area_size(field_radius);
+    let tile = area_size(radar_radius);
+    (field + tile - 1) / tile
+}
+
+// The complete cover with the fewest centres of the ones we try, in no particular order
+pub fn plan_cover(field_radius: i16, radar_radius: i16) -> Vec<Pos> {
+    let r = radar_radius;
+    let bases = vec![
+        (Pos::new(2 * r + 1, -r), Pos::new(r, r + 1)),
+        (Pos::new(-r, 2 * r + 1), Pos::new(r + 1, r)),
+    ];
+
+    let mut best: Option<Vec<Pos>> = None;
+    for &(a, b) in &bases {
+        // The hexes of one tile are a complete set of offsets for the lattice
+        for offset in Pos::origo().area(r) {
+            if let Some(plan) = lattice_plan(field_radius, r, a, b, offset) {
+                let is_better = match best {
+                    Some(ref current) => plan.len() < current.len(),
+                    None => true,
+                };
+                if is_better {
+                    best = Some(plan);
+                }
+            }
+        }
+    }
+
+    let plan = match best {
+        Some(plan) => plan,
+        None => greedy_cover(field_radius, radar_radius),
+    };
+    shrink(plan, field_radius, radar_radius)
+}
+
+// The tiles of one lattice touching the field, with centres moved inside the field.
+// None if some tile can't be covered from within the field.
+fn lattice_plan(field_radius: i16, r: i16, a: Pos, b: Pos, offset: Pos) -> Option<Vec<Pos>> {
+    let origo = Pos::origo();
+    // Plenty of lattice steps to span the field in every direction
+    let steps = field_radius / (r + 1) + 2;
+    let mut plan: Vec<Pos> = Vec::new();
+
+    for i in -steps..steps + 1 {
+        for j in -steps..steps + 1 {
+            let center = Pos::new(offset.x + i * a.x + j * b.x, offset.y + i * a.y + j * b.y);
+            let distance = center.distance(origo);
+            if distance > field_radius + r {
+                continue;
+            }
+            if distance <= field_radius {
+                plan.push(center);
+                continue;
+            }
+
+            // Find a centre inside the field that still sees the part of the tile that is
+            let tile: Vec<Pos> = center.area(r)
+                .into_iter()
+                .filter(|pos| pos.distance(origo) <= field_radius)
+                .collect();
+            let replacement = center.area(2 * r)
+                .into_iter()
+                .filter(|pos| pos.distance(origo) <= field_radius)
+                .filter(|pos| tile.iter().all(|t| t.distance(*pos) <= r))
+                .min_by_key(|pos| pos.distance(center));
+            match replacement {
+                Some(pos) => plan.push(pos),
+                None => return None,
+            }
+        }
+    }
+
+    Some(prune(plan, field_radius, r))
+}
+
+// Relocated centres overlap their neighbours, which can make some of them unnecessary
+fn prune(mut plan: Vec<Pos>, field_radius: i16, radar_radius: i16) -> Vec<Pos> {
+    let origo = Pos::origo();
+    let mut index = 0;
+    while index < plan.len() {
+        let is_redundant = plan[index].area(radar_radius)
+            .iter()
+            .filter(|pos| pos.distance(origo) <= field_radius)
+            .all(|pos| plan.iter()
+                .enumerate()
+                .any(|(other, center)| other != index && center.distance(*pos) <= radar_radius));
+        if is_redundant {
+            plan.remove(index);
+        } else {
+            index += 1;
+        }
+    }
+    plan
+}
+
+// Tries to do with one centre less until it can't: drops the centre that sees the fewest hexes
+// no other one does, then moves one centre at a time so it covers a hex left uncovered, the
+// move that uncovers the least for what it covers first. Moved centres stay put for a few
+// moves so the search doesn't go in circles. Gives up after SEARCH_MOVES moves.
+fn shrink(mut plan: Vec<Pos>, field_radius: i16, radar_radius: i16) -> Vec<Pos> {
+    let field = Pos::origo().area(field_radius);
+    let index: HashMap<Pos, usize> = field.iter().enumerate().map(|(i, &pos)| (pos, i)).collect();
+    // What a centre on each hex sees, and so also the centres that see each hex
+    let sees: Vec<HexSet> = field.iter()
+        .map(|pos| HexSet::of(field.len(), pos.area(radar_radius).iter().filter_map(|p| index.get(p).cloned())))
+        .collect();
+    let mut rng: StdRng = SeedableRng::from_seed(&[field_radius as usize, radar_radius as usize][..]);
+
+    while plan.len() > lower_bound(field_radius, radar_radius) {
+        let mut centres: Vec<usize> = plan.iter().map(|pos| index[pos]).collect();
+        let mut cover = Cover::new(field.len());
+        for &centre in &centres {
+            cover.add(&sees[centre]);
+        }
+        let dropped = (0..centres.len()).min_by_key(|&i| sees[centres[i]].common(&cover.once)).unwrap();
+        cover.remove(&sees[centres.remove(dropped)]);
+
+        let mut still_until = vec![0; field.len()];
+        for step in 1..SEARCH_MOVES + 1 {
+            let uncovered = cover.uncovered.to_vec();
+            if uncovered.is_empty() {
                 break;
             }
+            let target = uncovered[rng.gen_range(0, uncovered.len())];
+
+            // What each place to go would cover if no centre moved away
+            let moves: Vec<(usize, isize)> = sees[target].to_vec()
+                .into_iter()
+                .map(|to| (to, sees[to].common(&cover.uncovered) as isize))
+                .collect();
+
+            // (covered - uncovered, which centre, where to)
+            let mut best: Option<(isize, usize, usize)> = None;
+            let mut ties = 0;
+            for i in 0..centres.len() {
+                let centre = centres[i];
+                if still_until[centre] > step {
+                    continue;
+                }
+                // What only this centre sees is uncovered once it moves, unless it moves close
+                // enough to still see some of it
+                let only = sees[centre].and(&cover.once);
+                let lost = only.len() as isize;
+                for &(to, gained) in &moves {
+                    let kept = if field[to].distance(field[centre]) > 2 * radar_radius { 0 } else { sees[to].common(&only) };
+                    let gain = gained + kept as isize - lost;
+                    let is_better = match best {
+                        Some((best_gain, _, _)) if gain < best_gain => false,
+                        Some((best_gain, _, _)) if gain == best_gain => {
+                            ties += 1;
+                            rng.gen_range(0, ties + 1) == 0
+                        }
+                        _ => {
+                            ties = 0;
+                            true
+                        }
+                    };
+                    if is_better {
+                        best = Some((gain, i, to));
+                    }
+                }
+            }
+
+            if let Some((_, i, to)) = best {
+                cover.remove(&sees[centres[i]]);
+                cover.add(&sees[to]);
+                centres[i] = to;
+                still_until[to] = step + STILL_MOVES;
+            }
         }
-        return self.radar_positions.clone();
+
+        if !cover.uncovered.is_empty() {
+            break;
+        }
+        plan = prune(centres.iter().map(|&centre| field[centre]).collect(), field_radius, radar_radius);
+    }
+    plan
+}
+
+// Hexes of the field by their index in it, as bits so overlaps are quick to count
+#[derive(Clone)]
+struct HexSet {
+    words: Vec<u64>,
+}
+
+impl HexSet {
+    fn new(size: usize) -> HexSet {
+        HexSet { words: vec![0; (size + 63) / 64] }
+    }
+
+    fn of<I: Iterator<Item=usize>>(size: usize, hexes: I) -> HexSet {
+        let mut set = HexSet::new(size);
+        for hex in hexes {
+            set.set(hex, true);
+        }
+        set
+    }
+
+    fn set(&mut self, hex: usize, on: bool) {
+        if on {
+            self.words[hex / 64] |= 1 << (hex % 64);
+        } else {
+            self.words[hex / 64] &= !(1 << (hex % 64));
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.words.iter().fold(0, |acc, word| acc + word.count_ones() as usize)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.words.iter().all(|&word| word == 0)
+    }
+
+    fn and(&self, other: &HexSet) -> HexSet {
+        HexSet { words: self.words.iter().zip(other.words.iter()).map(|(a, b)| a & b).collect() }
+    }
+
+    // How many hexes are in both
+    fn common(&self, other: &HexSet) -> usize {
+        self.words.iter().zip(other.words.iter()).fold(0, |acc, (a, b)| acc + (a & b).count_ones() as usize)
+    }
+
+    fn to_vec(&self) -> Vec<usize> {
+        let mut hexes = Vec::new();
+        for (i, &word) in self.words.iter().enumerate() {
+            for bit in 0..64 {
+                if word & (1 << bit) != 0 {
+                    hexes.push(i * 64 + bit);
+                }
+            }
+        }
+        hexes
+    }
+}
+
+// How many centres of a plan see each hex, and the hexes no centre or only one sees
+struct Cover {
+    seen_by: Vec<usize>,
+    uncovered: HexSet,
+    once: HexSet,
+}
+
+impl Cover {
+    fn new(size: usize) -> Cover {
+        Cover { seen_by: vec![0; size], uncovered: HexSet::of(size, 0..size), once: HexSet::new(size) }
+    }
+
+    fn add(&mut self, seen: &HexSet) {
+        for hex in seen.to_vec() {
+            self.seen_by[hex] += 1;
+            self.update(hex);
+        }
+    }
+
+    fn remove(&mut self, seen: &HexSet) {
+        for hex in seen.to_vec() {
+            self.seen_by[hex] -= 1;
+            self.update(hex);
+        }
+    }
+
+    fn update(&mut self, hex: usize) {
+        self.uncovered.set(hex, self.seen_by[hex] == 0);
+        self.once.set(hex, self.seen_by[hex] == 1);
     }
 }
 
-fn get_next_position(mut pass: RadarPass) -> RadarPass {
-    let last_pos = pass.last_pos.unwrap();
+// Fallback that always works: keep picking the centre that covers the most uncovered hexes
+fn greedy_cover(field_radius: i16, radar_radius: i16) -> Vec<Pos> {
+    let field = Pos::origo().area(field_radius);
+    let mut uncovered: HashSet<Pos> = field.iter().cloned().collect();
+    let mut plan: Vec<Pos> = Vec::new();
 
-    if last_pos.x >= pass.meta.row_end {
-        pass.meta.row_start = pass.meta.row_start + pass.meta.row_start_change;
-        pass.meta.row_end = pass.meta.row_end + pass.meta.row_end_change;
+    while !uncovered.is_empty() {
+        let center = field.iter()
+            .cloned()
+            .max_by_key(|pos| pos.area(radar_radius).iter().filter(|p| uncovered.contains(p)).count())
+            .unwrap();
+        for pos in center.area(radar_radius) {
+            uncovered.remove(&pos);
+        }
+        plan.push(center);
+    }
+    prune(plan, field_radius, radar_radius)
+}
+
+// Enemies start anywhere on the field with equal probability, so a scan is expected to find
+// more the more unscanned hexes it covers. Orders the plan greedily by that, preferring
+// centres closer to the middle when it's a tie.
+pub fn order_by_discovery(plan: &Vec<Pos>, field_radius: i16, radar_radius: i16) -> Vec<Pos> {
+    let origo = Pos::origo();
+    let mut uncovered: HashSet<Pos> = origo.area(field_radius).into_iter().collect();
+    let mut left: Vec<Pos> = plan.clone();
+    let mut ordered: Vec<Pos> = Vec::new();
 
-        if pass.meta.row_start <= -(pass.meta.field_radius) {
-            let subtract_y = if (last_pos.y + 4) > 0 { last_pos.y + 4 } else { 0 };
-            pass.meta.row_start = -(pass.meta.field_radius - pass.meta.radar_radius);
-            pass.meta.row_start_change = 0;
-            pass.meta.row_end_change = -(pass.meta.radar_radius + 1);
-            pass.meta.row_end = pass.meta.field_radius - subtract_y - pass.meta.radar_radius;
+    while !left.is_empty() {
+        let index = (0..left.len())
+            .max_by_key(|&i| {
+                let new_hexes = left[i].area(radar_radius).iter().filter(|p| uncovered.contains(p)).count();
+                (new_hexes, -left[i].distance(origo))
+            })
+            .unwrap();
+        let center = left.remove(index);
+        for pos in center.area(radar_radius) {
+            uncovered.remove(&pos);
         }
+        ordered.push(center);
+    }
+    ordered
+}
 
-        // When the y coord is larger than the radius let's exit
-        if last_pos.y + pass.meta.radar_radius + 1  > pass.meta.field_radius {
-            pass.last_pos = None;
-            return pass;
+#[cfg(test)]
+fn assert_complete_cover(field_radius: i16, radar_radius: i16) {
+    let plan = plan_cover(field_radius, radar_radius);
+    let covered: HashSet<Pos> = plan.iter().flat_map(|c| c.area(radar_radius)).collect();
+    for pos in Pos::origo().area(field_radius) {
+        assert!(covered.contains(&pos), "{} not covered for field {} radar {}", pos, field_radius, radar_radius);
+    }
+    for center in &plan {
+        assert!(center.distance(Pos::origo()) <= field_radius, "{} is off the field", center);
+    }
+    // Every centre sees something no other one does
+    assert_eq!(prune(plan.clone(), field_radius, radar_radius).len(), plan.len());
+}
+
+#[test]
+fn radar_plan_covers_every_hex() {
+    for field_radius in 1..17 {
+        for radar_radius in 0..5 {
+            assert_complete_cover(field_radius, radar_radius);
         }
-        pass.last_pos = Some(Pos {
-            x: pass.meta.row_start,
-            y: last_pos.y + (pass.meta.radar_radius + 1)
-        });
-        return pass;
     }
-    pass.last_pos = Some(Pos {
-        x: last_pos.x + 4,
-        y: last_pos.y,
-    });
-    return pass;
 }
+
+#[test]
+fn radar_plan_is_minimal_when_the_bound_is_reachable() {
+    for field_radius in 1..8 {
+        // One scan sees the whole field, and a radar that sees one hex has to visit all of them
+        assert_eq!(plan_cover(field_radius, field_radius + 1).len(), lower_bound(field_radius, field_radius + 1));
+        assert_eq!(plan_cover(field_radius, field_radius).len(), 1);
+        assert_eq!(plan_cover(field_radius, 0).len(), lower_bound(field_radius, 0));
+    }
+}
+
+#[test]
+fn radar_plan_beats_the_old_walker() {
+    // The row walker used 50 positions for radius 14 with radar 2 (and missed 7 hexes),
+    // and 37 positions with radar 3
+    assert!(plan_cover(14, 2).len() < 50);
+    assert!(plan_cover(14, 3).len() < 37);
+}
+
+#[test]
+fn radar_plan_needs_fewer_centres_than_the_tiling() {
+    // The best tilings take 24 and 43 centres, no cover can do with fewer than 18 and 34
+    assert_eq!(lower_bound(14, 3), 18);
+    assert_eq!(lower_bound(14, 2), 34);
+    assert!(plan_cover(14, 3).len() <= 22);
+    assert!(plan_cover(14, 2).len() <= 42);
+}
+
+#[test]
+fn radar_order_scans_full_tiles_first() {
+    let plan = plan_cover(14, 3);
+    let ordered = order_by_discovery(&plan, 14, 3);
+    assert_eq!(ordered.len(), plan.len());
+    let first: HashSet<Pos> = ordered[0].area(3).into_iter().collect();
+    assert!(first.iter().all(|pos| pos.distance(Pos::origo()) <= 14));
+}
+
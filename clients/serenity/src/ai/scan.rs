@@ -1,20 +1,21 @@
-use defs::Action;
+use defs::{Action, ActionKind};
 use position::Pos;
-use strings::{RADAR, NOACTION};
 use ai::*;
 use patterns::smart_scan_spread;
 use util;
 use lists::{ ActionsList, HistoryList, AsteroidList, Decision };
 use lists::ActionMode::*;
 
+// Expected number of enemies within radar range of the hottest heatmap spot
+// before we prefer it over the next position in the scan sequence
+const HEATMAP_SCAN_THRESHOLD: f32 = 0.5;
+
 impl Ai {
     // Might be good to keep around as an option?
     #[allow(dead_code)]
-    pub fn random_radars_action(&self, actions: &mut Vec<Action>) {
-        for bot in &self.bots {
-            if bot.alive {
-                actions.set_action_for(bot.id, RADAR, util::get_random_pos(&self.radar_positions.1));
-            }
+    pub fn random_radars_action(&mut self, actions: &mut Vec<Action>) {
+        for bot in self.bots.iter().filter(|bot| bot.alive) {
+            actions.set_action_for(bot.id, ActionKind::Radar, util::get_random_pos(&mut self.rng, &self.radar_positions.1));
         }
     }
 
@@ -25,7 +26,7 @@ impl Ai {
             .cloned()
             .filter(|bot| bot.alive && {
                 if let Some(ac) = actions.iter().find(|ac| ac.bot_id == bot.id) {
-                    ac.action_type == NOACTION.to_string()
+                    ac.kind == ActionKind::Noaction
                 } else {
                     false
                 }
@@ -49,10 +50,21 @@ impl Ai {
                 .zip(smart_scan_spread(unused_pos, idle_bots.len() as i16))
                 .map(|(&ref bot_id, ref pos)| {
                     self.logger.log(&format!("Scanning with Bot {} on {} b/c it was idle and we picked up a historic echo.", bot_id, pos), 2);
-                    actions.set_action_for(*bot_id, RADAR, pos.clamp(&self.config.field_radius))
+                    actions.set_action_for(*bot_id, ActionKind::Radar, pos.clamp(&self.config.field_radius))
                 })
                 .count();
         } else {
+            let mut idle_bots = idle_bots;
+
+            // If the heatmap is fairly sure where someone is, check that first
+            if let Some((pos, mass)) = self.heatmap.best_target(self.config.radar) {
+                if mass >= HEATMAP_SCAN_THRESHOLD && idle_bots.len() > 0 {
+                    let bot_id = idle_bots.remove(0);
+                    actions.set_action_for(bot_id, ActionKind::Radar, pos);
+                    self.logger.log(&format!("Scanning with Bot {} on {} b/c the heatmap expects {:.2} enemies there.", bot_id, pos, mass), 2);
+                }
+            }
+
             // Resume basic sequential scanning
             let (ref mut radar_index, ref positions) = self.radar_positions;
             for bot_id in idle_bots {
@@ -61,7 +73,7 @@ impl Ai {
                     *radar_index = 0;
                 }
                 let target = self.radar_positions.1[*radar_index as usize];
-                actions.set_action_for(bot_id, RADAR, target);
+                actions.set_action_for(bot_id, ActionKind::Radar, target);
                 *radar_index += 1;
 
                 self.logger.log(&format!("Scanning with Bot {} on {} b/c it was idle.", bot_id, target), 2);
@@ -0,0 +1,238 @@
+use defs::Action;
+use ai::Ai;
+use lists::{ActionsList, Decision};
+use lists::ActionMode::*;
+
+// The strategies we run when nothing else is asked for, in order
+pub const DEFAULT_STRATEGIES: &'static str = "evade,aggressive,position,scan";
+
+// A strategy looks at the state of the game and proposes actions for the bots it wants.
+// Strategies are run in order by a Composer, and each one gets to see the actions the
+// previous ones settled on. A proposed action only replaces an earlier one if the strategy
+// has the same or higher priority than the one that claimed the bot first.
+pub trait Strategy {
+    fn name(&self) -> &'static str;
+
+    fn priority(&self) -> i16;
+
+    // Whether the strategy wants to run at all this round
+    fn evaluate(&self, _ai: &Ai, _decision: &Decision) -> bool {
+        true
+    }
+
+    // Set actions for the bots this strategy wants to use
+    fn decide(&mut self, ai: &mut Ai, actions: &mut Vec<Action>, decision: &mut Decision);
+}
+
+// Let each bot evade as needed
+pub struct Evade;
+
+impl Strategy for Evade {
+    fn name(&self) -> &'static str { "evade" }
+    fn priority(&self) -> i16 { 1 }
+
+    fn decide(&mut self, ai: &mut Ai, actions: &mut Vec<Action>, _decision: &mut Decision) {
+        ai.evade_if_needed(actions);
+    }
+}
+
+// Attack if we have a target, overriding evasion for healthy bots
+pub struct AggressiveAttack;
+
+impl Strategy for AggressiveAttack {
+    fn name(&self) -> &'static str { "aggressive" }
+    fn priority(&self) -> i16 { 2 }
+
+    fn decide(&mut self, ai: &mut Ai, actions: &mut Vec<Action>, decision: &mut Decision) {
+        ai.aggressive_attack_strategy(actions, decision);
+    }
+}
+
+// Move bots nobody wants out of sight of the enemies we're tracking
+pub struct Position;
+
+impl Strategy for Position {
+    fn name(&self) -> &'static str { "position" }
+    fn priority(&self) -> i16 { 0 }
+
+    fn decide(&mut self, ai: &mut Ai, actions: &mut Vec<Action>, _decision: &mut Decision) {
+        ai.position_idle_bots(actions);
+    }
+}
+
+// If not attacking, use bots that have nothing to do to scan in a sequence
+pub struct ScanWithIdleBots;
+
+impl Strategy for ScanWithIdleBots {
+    fn name(&self) -> &'static str { "scan" }
+    fn priority(&self) -> i16 { 0 }
+
+    fn evaluate(&self, _ai: &Ai, decision: &Decision) -> bool {
+        decision.mode != Attack
+    }
+
+    fn decide(&mut self, ai: &mut Ai, actions: &mut Vec<Action>, decision: &mut Decision) {
+        ai.scan_with_idle_bots(actions, decision);
+    }
+}
+
+// Radar random positions from the scan sequence with every bot nobody else wants
+pub struct RandomScan;
+
+impl Strategy for RandomScan {
+    fn name(&self) -> &'static str { "random-scan" }
+    fn priority(&self) -> i16 { 0 }
+
+    fn evaluate(&self, _ai: &Ai, decision: &Decision) -> bool {
+        decision.mode != Attack
+    }
+
+    fn decide(&mut self, ai: &mut Ai, actions: &mut Vec<Action>, decision: &mut Decision) {
+        decision.mode = Scan;
+        ai.random_radars_action(actions);
+    }
+}
+
+// Every strategy we know of, by the name used on the command line
+pub fn from_name(name: &str) -> Option<Box<Strategy>> {
+    match name {
+        "evade" => Some(Box::new(Evade)),
+        "aggressive" => Some(Box::new(AggressiveAttack)),
+        "position" => Some(Box::new(Position)),
+        "scan" => Some(Box::new(ScanWithIdleBots)),
+        "random-scan" => Some(Box::new(RandomScan)),
+        _ => None,
+    }
+}
+
+pub struct Composer {
+    strategies: Vec<Box<Strategy>>,
+}
+
+impl Composer {
+    pub fn new() -> Composer {
+        Composer { strategies: Vec::new() }
+    }
+
+    // Builds a composer from a comma separated list of strategy names like "evade,aggressive,scan".
+    // Returns the first name we don't know as the error.
+    pub fn from_names(names: &str) -> Result<Composer, String> {
+        let mut composer = Composer::new();
+        for name in names.split(',').map(|name| name.trim()).filter(|name| !name.is_empty()) {
+            match from_name(name) {
+                Some(strategy) => composer.register(strategy),
+                None => return Err(name.to_string()),
+            }
+        }
+        Ok(composer)
+    }
+
+    pub fn register(&mut self, strategy: Box<Strategy>) {
+        self.strategies.push(strategy);
+    }
+
+    pub fn names(&self) -> Vec<&'static str> {
+        self.strategies.iter().map(|strategy| strategy.name()).collect()
+    }
+
+    pub fn run(&mut self, ai: &mut Ai, actions: &mut Vec<Action>, decision: &mut Decision) {
+        // (bot_id, priority) of the strategy that has set the bot's action so far
+        let mut claims: Vec<(i16, i16)> = Vec::new();
+
+        for strategy in self.strategies.iter_mut() {
+            if !strategy.evaluate(ai, decision) {
+                continue;
+            }
+
+            let mut proposed = actions.clone();
+            strategy.decide(ai, &mut proposed, decision);
+            let priority = strategy.priority();
+
+            for action in proposed {
+                // A bot nobody has given an action yet counts as changed too
+                let changed = match actions.get_action(action.bot_id) {
+                    Some(current) => current.kind != action.kind || current.pos != action.pos,
+                    None => true,
+                };
+                if !changed {
+                    continue;
+                }
+
+                let claimed_by = claims.iter().find(|claim| claim.0 == action.bot_id).map(|claim| claim.1);
+                match claimed_by {
+                    Some(claimed) if claimed > priority => {
+                        ai.logger.log(&format!("Strategy {} can't use Bot {}, it's already taken.", strategy.name(), action.bot_id), 2);
+                    }
+                    _ => {
+                        claims.retain(|claim| claim.0 != action.bot_id);
+                        claims.push((action.bot_id, priority));
+                        if actions.get_action(action.bot_id).is_some() {
+                            actions.set_action_for(action.bot_id, action.kind, action.pos);
+                        } else {
+                            actions.push(action);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Default for Composer {
+    fn default() -> Composer {
+        Composer::from_names(DEFAULT_STRATEGIES).unwrap()
+    }
+}
+
+#[test]
+fn composer_knows_strategy_names() {
+    let composer = Composer::from_names("evade, scan").unwrap();
+    assert_eq!(composer.names(), vec!["evade", "scan"]);
+    assert_eq!(Composer::from_names("evade,telepathy").err(), Some("telepathy".to_string()));
+}
+
+// Sets the same action for a few bots, for testing the composer
+#[cfg(test)]
+struct Stub {
+    priority: i16,
+    kind: ::defs::ActionKind,
+    bot_ids: Vec<i16>,
+}
+
+#[cfg(test)]
+impl Strategy for Stub {
+    fn name(&self) -> &'static str { "stub" }
+    fn priority(&self) -> i16 { self.priority }
+
+    fn decide(&mut self, _ai: &mut Ai, actions: &mut Vec<Action>, _decision: &mut Decision) {
+        for &bot_id in &self.bot_ids {
+            actions.retain(|action| action.bot_id != bot_id);
+            actions.push(Action { bot_id: bot_id, kind: self.kind, pos: ::position::Pos::new(bot_id, 0) });
+        }
+    }
+}
+
+#[test]
+fn composer_lets_only_same_or_higher_priority_take_a_bot() {
+    use defs::ActionKind;
+    use log::Logger;
+    use sim::{default_config, Simulator};
+
+    let sim = Simulator::new(default_config(), &vec!["Serenity".to_string(), "Reavers".to_string()], 1);
+    let mut ai = Ai::new(&sim.start_message(0), Logger::disabled(), 1);
+    let mut composer = Composer::new();
+    // Bots without an action yet are taken by whoever asks first
+    composer.register(Box::new(Stub { priority: 1, kind: ActionKind::Move, bot_ids: vec![0, 1] }));
+    // Higher priority takes bot 1, and lower priority can't take it back
+    composer.register(Box::new(Stub { priority: 2, kind: ActionKind::Cannon, bot_ids: vec![1] }));
+    composer.register(Box::new(Stub { priority: 0, kind: ActionKind::Radar, bot_ids: vec![1, 2] }));
+    // Same priority can take a bot over
+    composer.register(Box::new(Stub { priority: 1, kind: ActionKind::Radar, bot_ids: vec![0] }));
+
+    let mut actions = Vec::new();
+    composer.run(&mut ai, &mut actions, &mut Decision::with_defaults());
+
+    actions.sort_by_key(|action| action.bot_id);
+    let kinds: Vec<(i16, ActionKind)> = actions.iter().map(|action| (action.bot_id, action.kind)).collect();
+    assert_eq!(kinds, vec![(0, ActionKind::Radar), (1, ActionKind::Cannon), (2, ActionKind::Radar)]);
+}
@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+use defs::{Event, EventKind};
+use position::Pos;
+use lists::{AsteroidList, HistoryList};
+use ai::Ai;
+
+// How likely the enemy is to shoot at a bot it knows the position of, by how it knows
+const P_DETECTED: f32 = 0.8;
+const P_DAMAGED: f32 = 0.7;
+const P_SEEN: f32 = 0.6;
+// Bots on hexes more threatened than this get out of there
+pub const EVADE_THRESHOLD: f32 = 0.3;
+// What standing in the blast of one of our other bots adds to a hex's threat: a shot at
+// either of us hits both
+const FRIENDLY_BLAST: f32 = 0.2;
+
+// For every hex, the probability that an enemy cannon blast reaches it next round.
+// Hexes we have no reason to worry about are left out and count as 0.
+pub struct ThreatMap {
+    cells: HashMap<Pos, f32>,
+    cannon: i16,
+}
+
+impl ThreatMap {
+    pub fn new(cannon: i16) -> ThreatMap {
+        ThreatMap { cells: HashMap::new(), cannon: cannon }
+    }
+
+    pub fn get(&self, pos: Pos) -> f32 {
+        *self.cells.get(&pos).unwrap_or(&0.0)
+    }
+
+    // The enemy shoots at `aim` with the given probability. Its blast covers config.cannon
+    // around it, and they tend to spread their shots a hex further out.
+    pub fn add_shot(&mut self, aim: Pos, probability: f32) {
+        for pos in aim.area(self.cannon + 1) {
+            let p = if pos.distance(aim) <= self.cannon { probability } else { probability / 2.0 };
+            let threat = self.cells.entry(pos).or_insert(0.0);
+            // Shots are independent, the hex is safe only if every one of them misses
+            *threat = 1.0 - (1.0 - *threat) * (1.0 - p);
+        }
+    }
+
+    // Purpose: the hex a bot on `from` should move to. The least threatened hex it can reach,
+    // counting the blast of our other bots (`friends`) as a threat too, and the closest one
+    // of those.
+    pub fn safest<A: AsteroidList>(&self, from: Pos, moves_allowed: i16, field_radius: i16,
+                                   friends: &Vec<Pos>, asteroids: &A) -> Pos {
+        from.area(moves_allowed)
+            .into_iter()
+            .filter(|pos| pos.distance(Pos::origo()) <= field_radius && !asteroids.is_asteroid(*pos))
+            .fold(None, |acc: Option<(f32, i16, Pos)>, pos| {
+                let curr = (self.with_friends(pos, friends), pos.distance(from), pos);
+                match acc {
+                    Some(best) => if curr.0 < best.0 || (curr.0 == best.0 && curr.1 < best.1) { Some(curr) } else { Some(best) },
+                    None => Some(curr),
+                }
+            })
+            .map(|(_, _, pos)| pos)
+            .unwrap_or(from)
+    }
+
+    fn with_friends(&self, pos: Pos, friends: &Vec<Pos>) -> f32 {
+        friends.iter()
+            .filter(|friend| friend.distance(pos) <= self.cannon)
+            .fold(self.get(pos), |threat, _| 1.0 - (1.0 - threat) * (1.0 - FRIENDLY_BLAST))
+    }
+}
+
+impl Ai {
+    // Purpose: guess where the enemy will shoot next round. They shoot where they know we are:
+    // bots their radar found, bots they hit (the harder, the better their aim) and bots
+    // standing in sight of one of the enemies we saw this round.
+    pub fn threat_map(&self) -> ThreatMap {
+        let mut threats = ThreatMap::new(self.config.cannon);
+
+        for (event, _) in self.history.get_events( EventKind::Detected, 1 ) {
+            if let Some(bot) = event.bot_id().and_then(|id| self.get_bot(id)) {
+                threats.add_shot(bot.pos, P_DETECTED);
+            }
+        }
+        for (event, _) in self.history.get_events( EventKind::Damaged, 1 ) {
+            if let Event::Damaged(ref ev) = event {
+                if let Some(bot) = self.get_bot(ev.bot_id) {
+                    let aim = ev.damage as f32 / (1 + self.config.cannon) as f32;
+                    threats.add_shot(bot.pos, P_DAMAGED * aim.min(1.0));
+                }
+            }
+        }
+        let enemies: Vec<Pos> = self.live_tracks()
+            .iter()
+            .filter(|track| track.last_seen == self.round_id)
+            .map(|track| track.pos)
+            .collect();
+        for bot in self.get_live_bots() {
+            if enemies.iter().any(|enemy| enemy.distance(bot.pos) <= self.config.see) {
+                threats.add_shot(bot.pos, P_SEEN);
+            }
+        }
+        threats
+    }
+}
+
+#[test]
+fn threat_map_adds_up_shots() {
+    let mut threats = ThreatMap::new(1);
+    threats.add_shot(Pos::origo(), 0.5);
+    threats.add_shot(Pos::new(1, 0), 0.5);
+    assert_eq!(threats.get(Pos::origo()), 0.75);
+    assert_eq!(threats.get(Pos::new(-2, 0)), 0.25);
+    assert_eq!(threats.get(Pos::new(5, 5)), 0.0);
+}
+
+#[test]
+fn safest_hex_is_out_of_the_blast() {
+    let asteroids: Vec<(Pos, bool)> = vec![(Pos::new(-2, 0), true)];
+    let mut threats = ThreatMap::new(1);
+    threats.add_shot(Pos::origo(), 0.8);
+
+    // The spread still reaches two hexes out, but that's better than taking the whole blast
+    let pos = threats.safest(Pos::origo(), 2, 14, &vec![Pos::new(0, 2)], &asteroids);
+    assert!((threats.get(pos) - 0.4).abs() < 0.001);
+    assert_eq!(pos.distance(Pos::origo()), 2);
+    assert!(pos != Pos::new(-2, 0) && pos.distance(Pos::new(0, 2)) > 1);
+}
+
+#[test]
+fn safest_hex_can_be_next_to_a_friend() {
+    let asteroids: Vec<(Pos, bool)> = Vec::new();
+    let mut threats = ThreatMap::new(1);
+    threats.add_shot(Pos::new(-1, 1), 0.8);
+
+    // Every hex we can reach is in the blast of a friend, the ones in the spread are still
+    // better than staying in the middle of the shot
+    let pos = threats.safest(Pos::origo(), 1, 14, &vec![Pos::new(1, 0), Pos::new(-1, 0)], &asteroids);
+    assert!((threats.get(pos) - 0.4).abs() < 0.001);
+}
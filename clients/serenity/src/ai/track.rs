@@ -0,0 +1,242 @@
+use defs::Event;
+use defs::Event::*;
+use position::Pos;
+use lists::AsteroidList;
+use ai::Ai;
+use std::cmp::Ordering::Equal;
+
+// A track we haven't seen for this many rounds is gone, the bot could be anywhere by now
+const MAX_AGE: i16 = 3;
+// Confidence of a track we just started, and below which we forget it
+const NEW_CONFIDENCE: f32 = 0.5;
+const MIN_CONFIDENCE: f32 = 0.1;
+
+// An enemy bot we have followed over one or more rounds. Echoes don't say which bot they
+// are, so a track is our guess that a row of echoes came from the same bot.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Track {
+    pub id: usize,
+    pub pos: Pos,
+    // Known once a see event has told us
+    pub bot_id: Option<i16>,
+    // The round we last saw something where we think the bot is
+    pub last_seen: i16,
+    // How many rounds in a row we've been able to follow it
+    pub length: usize,
+    // 0..1, how much we believe there still is a bot there
+    pub confidence: f32,
+}
+
+impl Track {
+    pub fn age(&self, round_id: i16) -> i16 {
+        round_id - self.last_seen
+    }
+
+    // Whether a bot on this track could have got to pos by round_id
+    fn could_reach(&self, pos: Pos, round_id: i16, moves_allowed: i16) -> bool {
+        self.pos.distance(pos) <= moves_allowed * self.age(round_id)
+    }
+
+    fn confirm(&mut self, pos: Pos, bot_id: Option<i16>, round_id: i16) {
+        self.pos = pos;
+        self.bot_id = bot_id.or(self.bot_id);
+        self.last_seen = round_id;
+        self.length += 1;
+        self.confidence += (1.0 - self.confidence) / 2.0;
+    }
+}
+
+// Something we saw this round that could be an enemy bot
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Sighting {
+    pos: Pos,
+    bot_id: Option<i16>,
+}
+
+pub struct Tracker {
+    tracks: Vec<Track>,
+    next_id: usize,
+}
+
+impl Tracker {
+    pub fn new() -> Tracker {
+        Tracker { tracks: Vec::new(), next_id: 0 }
+    }
+
+    // Every track we still believe in, the most believable first
+    pub fn live(&self) -> Vec<Track> {
+        let mut tracks = self.tracks.clone();
+        tracks.sort_by(|a, b| {
+            match b.last_seen.cmp(&a.last_seen) {
+                Equal => match b.confidence.partial_cmp(&a.confidence).unwrap() {
+                    Equal => a.id.cmp(&b.id),
+                    order => order,
+                },
+                order => order,
+            }
+        });
+        tracks
+    }
+
+    // The live tracks we saw something of in round_id
+    #[allow(dead_code)]
+    pub fn seen_in(&self, round_id: i16) -> Vec<Track> {
+        self.live().into_iter().filter(|track| track.last_seen == round_id).collect()
+    }
+
+    // Purpose: tie this round's echoes and see events to the tracks we had. A see event
+    // belongs to the track of the same bot, everything else goes to the closest track that
+    // could have moved there. Whatever is left over starts a new track.
+    pub fn update<A: AsteroidList>(&mut self, round_id: i16, events: &Vec<Event>, moves_allowed: i16, asteroids: &A) {
+        let mut sightings: Vec<Sighting> = Vec::new();
+        for event in events {
+            match *event {
+                See(ref ev) => {
+                    // An echo on the same hex is the same bot, keep the one that knows the id
+                    sightings.retain(|s| s.pos != ev.pos);
+                    sightings.push(Sighting { pos: ev.pos, bot_id: Some(ev.bot_id) });
+                }
+                Echo(ref ev) => {
+                    if !sightings.iter().any(|s| s.pos == ev.pos) {
+                        sightings.push(Sighting { pos: ev.pos, bot_id: None });
+                    }
+                }
+                Die(ref ev) => self.tracks.retain(|track| track.bot_id != Some(ev.bot_id)),
+                _ => (),
+            }
+        }
+
+        let mut matched: Vec<usize> = Vec::new();
+        let mut unmatched: Vec<Sighting> = Vec::new();
+
+        // Bot ids first, those we can't get wrong
+        for sighting in sightings {
+            let index = sighting.bot_id.and_then(|bot_id| {
+                self.tracks.iter().position(|track| track.bot_id == Some(bot_id))
+            });
+            match index {
+                Some(i) => {
+                    self.tracks[i].confirm(sighting.pos, sighting.bot_id, round_id);
+                    matched.push(i);
+                }
+                None => unmatched.push(sighting),
+            }
+        }
+
+        // Then the closest pairs of track and sighting, as long as the bot could have made it
+        let mut pairs: Vec<(i16, usize, usize)> = Vec::new();
+        for (i, track) in self.tracks.iter().enumerate() {
+            if matched.contains(&i) {
+                continue;
+            }
+            for (j, sighting) in unmatched.iter().enumerate() {
+                let known_other = track.bot_id.is_some() && sighting.bot_id.is_some();
+                if !known_other && track.could_reach(sighting.pos, round_id, moves_allowed) {
+                    pairs.push((track.pos.distance(sighting.pos), i, j));
+                }
+            }
+        }
+        pairs.sort();
+
+        let mut used: Vec<usize> = Vec::new();
+        for (_, i, j) in pairs {
+            if matched.contains(&i) || used.contains(&j) {
+                continue;
+            }
+            self.tracks[i].confirm(unmatched[j].pos, unmatched[j].bot_id, round_id);
+            matched.push(i);
+            used.push(j);
+        }
+
+        for (j, sighting) in unmatched.into_iter().enumerate() {
+            if used.contains(&j) {
+                continue;
+            }
+            self.tracks.push(Track {
+                id: self.next_id,
+                pos: sighting.pos,
+                bot_id: sighting.bot_id,
+                last_seen: round_id,
+                length: 1,
+                confidence: NEW_CONFIDENCE,
+            });
+            self.next_id += 1;
+        }
+
+        // Tracks we lost this round fade away
+        for track in self.tracks.iter_mut() {
+            if track.last_seen != round_id {
+                track.confidence /= 2.0;
+            }
+        }
+        self.tracks.retain(|track| {
+            track.age(round_id) <= MAX_AGE
+                && track.confidence >= MIN_CONFIDENCE
+                && !asteroids.is_asteroid(track.pos)
+        });
+    }
+}
+
+impl Ai {
+    pub fn update_tracks(&mut self, events: &Vec<Event>) {
+        self.tracks.update(self.round_id, events, self.config.moves_allowed, &self.asteroids);
+
+        for track in self.tracks.live() {
+            self.logger.log(&format!("Track {} at {}, bot {:?}, {} rounds old, confidence {:.2}",
+                                     track.id, track.pos, track.bot_id, track.age(self.round_id), track.confidence), 2);
+        }
+    }
+
+    // The enemies we are following, the most believable first
+    pub fn live_tracks(&self) -> Vec<Track> {
+        self.tracks.live()
+    }
+}
+
+#[cfg(test)]
+fn echo(x: i16, y: i16) -> Event {
+    Echo(::defs::EchoEvent { pos: Pos::new(x, y) })
+}
+
+#[test]
+fn tracker_follows_moving_echoes() {
+    let asteroids: Vec<(Pos, bool)> = Vec::new();
+    let mut tracker = Tracker::new();
+    tracker.update(0, &vec![echo(0, 0), echo(8, 0)], 2, &asteroids);
+    tracker.update(1, &vec![echo(2, 0), echo(8, -1)], 2, &asteroids);
+
+    let tracks = tracker.seen_in(1);
+    assert_eq!(tracks.len(), 2);
+    assert!(tracks.iter().all(|track| track.length == 2 && track.confidence > NEW_CONFIDENCE));
+
+    // Too far for anyone to have moved there, so it's somebody new
+    tracker.update(2, &vec![echo(-8, 0)], 2, &asteroids);
+    let tracks = tracker.live();
+    assert_eq!(tracks[0].pos, Pos::new(-8, 0));
+    assert_eq!(tracks[0].length, 1);
+    assert_eq!(tracks.len(), 3);
+
+    // Lost ones fade and then are forgotten
+    for round in 3..7 {
+        tracker.update(round, &vec![], 2, &asteroids);
+    }
+    assert!(tracker.live().is_empty());
+}
+
+#[test]
+fn tracker_uses_bot_ids_from_see_events() {
+    let asteroids: Vec<(Pos, bool)> = Vec::new();
+    let see = |bot_id, x, y| See(::defs::SeeEvent { source: 0, bot_id: bot_id, pos: Pos::new(x, y) });
+    let mut tracker = Tracker::new();
+    tracker.update(0, &vec![see(7, 0, 0), see(8, 1, 0)], 2, &asteroids);
+    // The closest match would swap them
+    tracker.update(1, &vec![see(7, 2, 0), see(8, 0, 0)], 2, &asteroids);
+
+    let tracks = tracker.seen_in(1);
+    assert_eq!(tracks.len(), 2);
+    let seven = tracks.iter().find(|track| track.bot_id == Some(7)).unwrap();
+    assert_eq!((seven.pos, seven.length), (Pos::new(2, 0), 2));
+
+    tracker.update(2, &vec![Die(::defs::DieEvent { bot_id: 7 })], 2, &asteroids);
+    assert_eq!(tracker.live().iter().map(|track| track.bot_id).collect::<Vec<_>>(), vec![Some(8)]);
+}
@@ -0,0 +1,322 @@
+use std::fmt;
+use defs::{Action, ActionKind, Config, Event};
+use position::Pos;
+use lists::{AsteroidList, HistoryList};
+use ai::Ai;
+use ai::asteroids::Evidence;
+use ai::bot::Bot;
+
+// Something in an action the server would ignore, or do differently than we meant
+#[derive(Debug, Clone, PartialEq)]
+pub enum Violation {
+    NoSuchBot,
+    DeadBot,
+    // The server only uses one action per bot
+    Duplicate,
+    MoveTooFar(i16),
+    MoveIntoAsteroid,
+    MoveInPlace,
+    // Moves outside the field get clamped to it, radars and cannons are ignored
+    OffField,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Violation::NoSuchBot => write!(f, "not our bot"),
+            Violation::DeadBot => write!(f, "bot is dead"),
+            Violation::Duplicate => write!(f, "bot already has an action"),
+            Violation::MoveTooFar(distance) => write!(f, "moving {} hexes", distance),
+            Violation::MoveIntoAsteroid => write!(f, "moving into an asteroid"),
+            Violation::MoveInPlace => write!(f, "moving to where the bot already is"),
+            Violation::OffField => write!(f, "outside the field"),
+        }
+    }
+}
+
+// What was wrong with an action and what we did about it
+#[derive(Debug, Clone)]
+pub struct Report {
+    pub action: Action,
+    pub violation: Violation,
+    // None if the action was dropped
+    pub repaired: Option<Action>,
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.repaired {
+            Some(ref action) => write!(f, "{}: {}, changed to {}", self.action, self.violation, action),
+            None => write!(f, "{}: {}, dropped", self.action, self.violation),
+        }
+    }
+}
+
+// Checks actions against the rules of the server and what we know of the field. Returns the
+// actions to send, with what could be fixed fixed and the rest dropped, and a report of
+// every change. The server uses the first action of each bot in the message, and our actions
+// are sent in reverse, so of a bot's actions we keep the last.
+pub fn validate_actions<A: AsteroidList>(actions: &Vec<Action>, bots: &Vec<Bot>, config: &Config, asteroids: &A)
+    -> (Vec<Action>, Vec<Report>)
+{
+    let mut valid: Vec<Action> = Vec::new();
+    let mut reports: Vec<Report> = Vec::new();
+
+    for (index, action) in actions.iter().enumerate() {
+        let report = |violation: Violation, repaired: Option<Action>| {
+            Report { action: action.clone(), violation: violation, repaired: repaired }
+        };
+
+        if actions[index + 1..].iter().any(|later| later.bot_id == action.bot_id) {
+            reports.push(report(Violation::Duplicate, None));
+            continue;
+        }
+        let bot = match bots.iter().find(|bot| bot.id == action.bot_id) {
+            Some(bot) => bot,
+            None => {
+                reports.push(report(Violation::NoSuchBot, None));
+                continue;
+            }
+        };
+        if !bot.alive {
+            reports.push(report(Violation::DeadBot, None));
+            continue;
+        }
+
+        let checked = match action.kind {
+            ActionKind::Move => check_move(action, bot, config, asteroids),
+            ActionKind::Cannon | ActionKind::Radar => check_target(action, config),
+            ActionKind::Noaction => None,
+        };
+        match checked {
+            None => valid.push(action.clone()),
+            Some((violation, repaired)) => {
+                if let Some(ref repaired) = repaired {
+                    valid.push(repaired.clone());
+                }
+                reports.push(report(violation, repaired));
+            }
+        }
+    }
+
+    (valid, reports)
+}
+
+fn is_on_field(pos: Pos, config: &Config) -> bool {
+    pos.distance(Pos::origo()) <= config.field_radius
+}
+
+fn with_pos(action: &Action, pos: Pos) -> Action {
+    Action { bot_id: action.bot_id, kind: action.kind, pos: pos }
+}
+
+// A bad move is changed to the allowed move closest to where the bot wanted to go,
+// or dropped if staying put is closer. The bot wanted to move, so a move that gets it
+// no closer still beats standing still.
+fn check_move<A: AsteroidList>(action: &Action, bot: &Bot, config: &Config, asteroids: &A)
+    -> Option<(Violation, Option<Action>)>
+{
+    let target = action.pos;
+    let distance = target.distance(bot.pos);
+    let violation = if !is_on_field(target, config) {
+        Violation::OffField
+    } else if distance == 0 {
+        return Some((Violation::MoveInPlace, None));
+    } else if distance > config.moves_allowed {
+        Violation::MoveTooFar(distance)
+    } else if asteroids.is_asteroid(target) {
+        Violation::MoveIntoAsteroid
+    } else {
+        return None;
+    };
+
+    let best = bot.pos.area(config.moves_allowed)
+        .into_iter()
+        .filter(|pos| *pos != bot.pos && is_on_field(*pos, config) && !asteroids.is_asteroid(*pos))
+        .min_by_key(|pos| pos.distance(target));
+    let repaired = match best {
+        Some(pos) if pos.distance(target) <= distance => Some(with_pos(action, pos)),
+        _ => None,
+    };
+    Some((violation, repaired))
+}
+
+// Radars and cannons outside the field are moved to the closest hex on it
+fn check_target(action: &Action, config: &Config) -> Option<(Violation, Option<Action>)> {
+    if is_on_field(action.pos, config) {
+        return None;
+    }
+    let closest = Pos::origo().area(config.field_radius)
+        .into_iter()
+        .min_by_key(|pos| pos.distance(action.pos))
+        .unwrap();
+    Some((Violation::OffField, Some(with_pos(action, closest))))
+}
+
+// Purpose: find the moves we sent that the server didn't carry out. It doesn't tell us, there's
+// just no move event for the bot. A move in place never gets one, anything else we sent is
+// either too far or into an asteroid. `bots` are where our bots were when the moves were sent,
+// and bots that died meanwhile don't count.
+// If our actions came too late the server plays the ones it got from us before, so when not a
+// single move of ours went through we can't tell what happened to them and find nothing.
+pub fn rejected_moves(sent: &Vec<Action>, events: &Vec<Event>, bots: &Vec<Bot>, config: &Config)
+    -> Vec<(Action, Violation)>
+{
+    let acknowledged = sent.iter()
+        .filter(|ac| ac.kind == ActionKind::Move)
+        .any(|ac| events.iter().any(|event| match *event {
+            Event::Move(ref ev) => ev.bot_id == ac.bot_id,
+            _ => false,
+        }));
+    if !acknowledged {
+        return Vec::new();
+    }
+
+    let mut rejected = Vec::new();
+    for action in sent.iter().filter(|ac| ac.kind == ActionKind::Move) {
+        let moved_or_died = events.iter().any(|event| match *event {
+            Event::Move(ref ev) => ev.bot_id == action.bot_id,
+            Event::Die(ref ev) => ev.bot_id == action.bot_id,
+            _ => false,
+        });
+        let bot = match bots.iter().find(|bot| bot.id == action.bot_id) {
+            Some(bot) if !moved_or_died => bot,
+            _ => continue,
+        };
+
+        let destination = action.pos.clamp(&config.field_radius);
+        let distance = bot.pos.distance(destination);
+        if distance == 0 {
+            continue;
+        } else if distance > config.moves_allowed {
+            rejected.push((action.clone(), Violation::MoveTooFar(distance)));
+        } else {
+            rejected.push((with_pos(action, destination), Violation::MoveIntoAsteroid));
+        }
+    }
+    rejected
+}
+
+impl Ai {
+    // Purpose: never send an action the server would quietly ignore. Everything we had to
+    // change is logged and kept for later inspection.
+    pub fn validate_actions(&mut self, actions: &Vec<Action>) -> Vec<Action> {
+        let (valid, reports) = validate_actions(actions, &self.bots, &self.config, &self.asteroids);
+        for report in reports {
+            self.logger.log(&format!("Invalid action {}", report), 1);
+            self.violations.push((self.round_id, report));
+        }
+        valid
+    }
+
+    // Purpose: notice the moves of last round that never happened. A move that wasn't too far
+    // can only have hit an asteroid, so we learn where one is. Call before the move events are
+    // applied to our bots.
+    pub fn check_moves(&mut self, events: &Vec<Event>) {
+        if self.round_id < 1 || self.round_id > self.history.len() as i16 {
+            return;
+        }
+        let sent = self.history.get_actions_for_round( ActionKind::Move, self.round_id - 1 );
+        for (action, violation) in rejected_moves(&sent, events, &self.bots, &self.config) {
+            self.logger.log(&format!("Move rejected {}: {}", action, violation), 1);
+            if violation != Violation::MoveIntoAsteroid {
+                continue;
+            }
+            if self.asteroids.add(action.pos, Evidence::BlockedMove) {
+                self.logger.log(&format!("Recorded an asteroid at {} (BlockedMove).", action.pos), 2);
+            } else {
+                self.logger.log(&format!("Recording that there might be an asteroid at {} (BlockedMove), evidence {:.2}.",
+                                         action.pos, self.asteroids.evidence(action.pos)), 2);
+            }
+        }
+    }
+
+    // Every action we had to change so far, with the round it was made in
+    #[allow(dead_code)]
+    pub fn violations(&self) -> &Vec<(i16, Report)> {
+        &self.violations
+    }
+}
+
+#[cfg(test)]
+fn test_bot(id: i16, x: i16, y: i16, alive: bool) -> Bot {
+    Bot { id: id, name: format!("Bot{}", id), alive: alive, pos: Pos::new(x, y), hp: 10 }
+}
+
+#[cfg(test)]
+fn test_action(bot_id: i16, kind: ActionKind, x: i16, y: i16) -> Action {
+    Action { bot_id: bot_id, kind: kind, pos: Pos::new(x, y) }
+}
+
+#[test]
+fn validate_repairs_moves() {
+    use sim::default_config;
+    let config = default_config();
+    let bots = vec![test_bot(1, 0, 0, true), test_bot(2, 14, 0, true)];
+    let asteroids: Vec<(Pos, bool)> = vec![(Pos::new(1, 0), true)];
+
+    let actions = vec![
+        test_action(1, ActionKind::Move, 5, 0),
+        test_action(2, ActionKind::Move, 15, 0),
+    ];
+    let (valid, reports) = validate_actions(&actions, &bots, &config, &asteroids);
+
+    assert_eq!(reports.len(), 2);
+    assert_eq!(reports[0].violation, Violation::MoveTooFar(5));
+    assert_eq!(valid[0].pos, Pos::new(2, 0));
+    // Nowhere on the field is closer to (15, 0) than where bot 2 already is
+    assert_eq!(reports[1].violation, Violation::OffField);
+    assert!(reports[1].repaired.is_none());
+    assert_eq!(valid.len(), 1);
+
+    let (valid, reports) = validate_actions(&vec![test_action(1, ActionKind::Move, 1, 0)], &bots, &config, &asteroids);
+    assert_eq!(reports[0].violation, Violation::MoveIntoAsteroid);
+    assert_eq!(valid.len(), 1);
+    assert!(!asteroids.is_asteroid(valid[0].pos));
+    assert_eq!(valid[0].pos.distance(Pos::new(0, 0)), 1);
+}
+
+#[test]
+fn validate_keeps_one_action_per_live_bot() {
+    use sim::default_config;
+    let config = default_config();
+    let bots = vec![test_bot(1, 0, 0, true), test_bot(2, 3, 0, false)];
+    let asteroids: Vec<(Pos, bool)> = Vec::new();
+
+    let actions = vec![
+        test_action(1, ActionKind::Radar, 3, 3),
+        test_action(1, ActionKind::Cannon, 20, 0),
+        test_action(2, ActionKind::Radar, 0, 0),
+        test_action(7, ActionKind::Radar, 0, 0),
+    ];
+    let (valid, reports) = validate_actions(&actions, &bots, &config, &asteroids);
+
+    let violations: Vec<Violation> = reports.iter().map(|report| report.violation.clone()).collect();
+    assert_eq!(violations, vec![Violation::Duplicate, Violation::OffField, Violation::DeadBot, Violation::NoSuchBot]);
+    assert_eq!(valid.len(), 1);
+    assert_eq!(valid[0].kind, ActionKind::Cannon);
+    assert_eq!(valid[0].pos, Pos::new(14, 0));
+}
+
+#[test]
+fn rejected_moves_are_found() {
+    let config = Config { field_radius: 14, moves_allowed: 2, ..Config::default() };
+    let bots = vec![test_bot(0, 0, 0, true), test_bot(1, 5, 0, true), test_bot(2, -5, 0, true), test_bot(3, 0, 5, true)];
+    let sent = vec![test_action(0, ActionKind::Move, 1, 0),
+                    test_action(1, ActionKind::Move, 9, 0),
+                    test_action(2, ActionKind::Move, -6, 0),
+                    test_action(3, ActionKind::Move, 0, 6)];
+    let events = vec![Event::Move(::defs::MoveEvent { bot_id: 2, pos: Pos::new(-6, 0) }),
+                      Event::Die(::defs::DieEvent { bot_id: 3 })];
+
+    let rejected: Vec<(i16, Pos, Violation)> = rejected_moves(&sent, &events, &bots, &config)
+        .into_iter()
+        .map(|(action, violation)| (action.bot_id, action.pos, violation))
+        .collect();
+    assert_eq!(rejected, vec![(0, Pos::new(1, 0), Violation::MoveIntoAsteroid),
+                              (1, Pos::new(9, 0), Violation::MoveTooFar(4))]);
+
+    // No move went through, so the server may have played older actions of ours instead
+    let events = vec![Event::Die(::defs::DieEvent { bot_id: 3 })];
+    assert!(rejected_moves(&sent, &events, &bots, &config).is_empty());
+}
@@ -0,0 +1,102 @@
+use defs::{Action, ActionKind, Config};
+use position::Pos;
+use lists::{ActionsList, AsteroidList};
+use ai::Ai;
+use ai::heatmap::Heatmap;
+
+// Tracks older than this are too stale to keep away from
+const MAX_THREAT_AGE: i16 = 1;
+
+// Whether an enemy on `enemy` could move this round and then see a bot on `pos`
+fn in_sight_of(pos: Pos, enemy: Pos, config: &Config) -> bool {
+    pos.distance(enemy) <= config.see + config.moves_allowed
+}
+
+// Purpose: pick the hex a bot on `from` should move to. Bots see each other passively within
+// config.see, so we want to stand where no enemy we know of can get in sight of us, while
+// the hexes we see hold as many of the enemies we don't know of as the heatmap expects.
+// `friends` are where our other bots will be, we don't stand in each other's blast.
+pub fn lookout<A: AsteroidList>(from: Pos, threats: &Vec<Pos>, friends: &Vec<Pos>, heatmap: &Heatmap,
+                                config: &Config, asteroids: &A) -> Pos {
+    let candidates: Vec<Pos> = from.area(config.moves_allowed)
+        .into_iter()
+        .filter(|pos| pos.distance(Pos::origo()) <= config.field_radius && !asteroids.is_asteroid(*pos))
+        .filter(|pos| friends.iter().all(|friend| friend.distance(*pos) > config.cannon))
+        .collect();
+
+    // (seen, enemies we'd see, how far we'd have to go, hex)
+    let mut best: Option<(bool, f32, i16, Pos)> = None;
+    for pos in candidates {
+        let seen = threats.iter().any(|&enemy| in_sight_of(pos, enemy, config));
+        let sight = pos.area(config.see).iter().fold(0.0, |acc, p| acc + heatmap.get(*p));
+        let steps = pos.distance(from);
+        let better = match best {
+            None => true,
+            Some((best_seen, best_sight, best_steps, _)) => {
+                if seen != best_seen {
+                    !seen
+                } else if sight != best_sight {
+                    sight > best_sight
+                } else {
+                    steps < best_steps
+                }
+            }
+        };
+        if better {
+            best = Some((seen, sight, steps, pos));
+        }
+    }
+    best.map(|(_, _, _, pos)| pos).unwrap_or(from)
+}
+
+impl Ai {
+    // Bots nobody has a use for and that could be seen by an enemy we're tracking move out of
+    // sight, to where their own eyes do the most good
+    pub fn position_idle_bots(&mut self, actions: &mut Vec<Action>) {
+        let threats: Vec<Pos> = self.live_tracks()
+            .iter()
+            .filter(|track| track.age(self.round_id) <= MAX_THREAT_AGE)
+            .map(|track| track.pos)
+            .collect();
+        if threats.is_empty() {
+            return;
+        }
+
+        for bot in self.get_live_bots() {
+            let idle = actions.get_action(bot.id).map_or(false, |ac| ac.kind == ActionKind::Noaction);
+            if !idle || !threats.iter().any(|&enemy| in_sight_of(bot.pos, enemy, &self.config)) {
+                continue;
+            }
+            let friends: Vec<Pos> = self.get_live_bots()
+                .iter()
+                .filter(|other| other.id != bot.id)
+                .map(|other| match actions.get_action(other.id) {
+                    Some(action) if action.kind == ActionKind::Move => action.pos,
+                    _ => other.pos,
+                })
+                .collect();
+            let destination = lookout(bot.pos, &threats, &friends, &self.heatmap, &self.config, &self.asteroids);
+            if destination != bot.pos {
+                actions.set_action_for(bot.id, ActionKind::Move, destination);
+                self.logger.log(&format!("Moving Bot {} to {} to keep out of sight", bot.id, destination), 2);
+            }
+        }
+    }
+}
+
+#[test]
+fn lookout_hides_and_watches() {
+    let asteroids: Vec<(Pos, bool)> = Vec::new();
+    let config = Config { field_radius: 14, moves_allowed: 2, cannon: 1, see: 2, ..Config::default() };
+    let mut heatmap = Heatmap::new(14, 1);
+    heatmap.observe_empty(Pos::origo(), 14);
+    heatmap.observe_bot(Pos::new(-4, 0));
+
+    let pos = lookout(Pos::origo(), &vec![Pos::new(3, 0)], &vec![], &heatmap, &config, &asteroids);
+    assert!(pos.distance(Pos::new(3, 0)) > config.see + config.moves_allowed);
+    assert!(pos.distance(Pos::new(-4, 0)) <= config.see);
+
+    // Never into a friend's blast
+    let pos = lookout(Pos::origo(), &vec![Pos::new(3, 0)], &vec![Pos::new(-3, 1)], &heatmap, &config, &asteroids);
+    assert!(pos.distance(Pos::new(-3, 1)) > config.cannon);
+}
@@ -0,0 +1,94 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::result;
+use std::str::Utf8Error;
+use serde_json;
+use websocket::result::WebSocketError;
+
+pub type SerenityResult<T> = result::Result<T, SerenityError>;
+
+#[derive(Debug)]
+pub enum SerenityError {
+    // Problems with the connection itself
+    Websocket(WebSocketError),
+    // The host and port don't make a websocket url, e.g. a host with a space in it
+    BadUrl { url: String, reason: String },
+    // The server sent a binary, ping etc. message where we wanted text
+    NotText,
+    Utf8(Utf8Error),
+    // The message wasn't the JSON we expected
+    Json(serde_json::Error),
+    // A message arrived out of order, e.g. events before start
+    UnexpectedMessage { expected: &'static str, got: String },
+    UnknownMessage(String),
+    // The server sent an error message, e.g. when we join a game that already started
+    Refused(String),
+    // A game log entry lacks a field its type needs, e.g. a start without a config
+    MissingField { entry: String, field: &'static str },
+    // Reading a file, e.g. a game log to replay
+    Io(io::Error),
+    NoSuchTeam(i16),
+}
+
+impl fmt::Display for SerenityError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SerenityError::Websocket(ref e) => write!(f, "Websocket error: {}", e),
+            SerenityError::BadUrl { ref url, ref reason } => write!(f, "Could not parse url {}: {}", url, reason),
+            SerenityError::NotText => write!(f, "Got a non-text message from server"),
+            SerenityError::Utf8(ref e) => write!(f, "Message is not UTF-8: {}", e),
+            SerenityError::Json(ref e) => write!(f, "Could not parse message: {}", e),
+            SerenityError::UnexpectedMessage { expected, ref got } =>
+                write!(f, "Expected a {} message, got {}", expected, got),
+            SerenityError::UnknownMessage(ref message) => write!(f, "Unknown message type {}", message),
+            SerenityError::Refused(ref reason) => write!(f, "The server refused us: {}", reason),
+            SerenityError::MissingField { ref entry, field } =>
+                write!(f, "Entry {} is missing {}", entry, field),
+            SerenityError::Io(ref e) => write!(f, "Could not read file: {}", e),
+            SerenityError::NoSuchTeam(team_id) => write!(f, "No team with id {} in the game", team_id),
+        }
+    }
+}
+
+impl Error for SerenityError {
+    fn description(&self) -> &str {
+        match *self {
+            SerenityError::Websocket(_) => "websocket error",
+            SerenityError::BadUrl { .. } => "bad url",
+            SerenityError::NotText => "non-text message",
+            SerenityError::Utf8(_) => "message is not UTF-8",
+            SerenityError::Json(_) => "malformed message",
+            SerenityError::UnexpectedMessage { .. } => "unexpected message",
+            SerenityError::UnknownMessage(_) => "unknown message type",
+            SerenityError::Refused(_) => "refused by the server",
+            SerenityError::MissingField { .. } => "entry is missing a field",
+            SerenityError::Io(_) => "could not read file",
+            SerenityError::NoSuchTeam(_) => "no such team",
+        }
+    }
+}
+
+impl From<WebSocketError> for SerenityError {
+    fn from(e: WebSocketError) -> SerenityError {
+        SerenityError::Websocket(e)
+    }
+}
+
+impl From<Utf8Error> for SerenityError {
+    fn from(e: Utf8Error) -> SerenityError {
+        SerenityError::Utf8(e)
+    }
+}
+
+impl From<serde_json::Error> for SerenityError {
+    fn from(e: serde_json::Error) -> SerenityError {
+        SerenityError::Json(e)
+    }
+}
+
+impl From<io::Error> for SerenityError {
+    fn from(e: io::Error) -> SerenityError {
+        SerenityError::Io(e)
+    }
+}
@@ -0,0 +1,189 @@
+use serde_json;
+use ai::Ai;
+use ai::strategy::Composer;
+use automaton::{Hooks, Incoming};
+use defs::{IncomingConnected, IncomingMessage, IncomingError, IncomingEvents, IncomingEnd, JoinMessage, Start, TIE_TEAM_ID};
+use error::{SerenityError, SerenityResult};
+use log::Logger;
+use options::Options;
+use strings::{CONNECTED, JOIN, START, EVENTS, END, ERROR};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Won,
+    Lost,
+    Tie,
+}
+
+// How a game went for us
+#[derive(Debug, Clone)]
+pub struct GameResult {
+    pub outcome: Outcome,
+    pub rounds: i16,
+    pub bots_alive: usize,
+}
+
+// Plays a game with the automaton, the Ai is made when the game starts
+pub struct Game {
+    options: Options,
+    ai: Option<Ai>,
+    rounds: i16,
+    result: Option<GameResult>,
+}
+
+impl Game {
+    pub fn new(options: &Options) -> Game {
+        Game {
+            options: options.clone(),
+            ai: None,
+            rounds: 0,
+            result: None,
+        }
+    }
+
+    // None until the game has ended
+    pub fn result(&self) -> Option<&GameResult> {
+        self.result.as_ref()
+    }
+}
+
+impl Hooks for Game {
+    type Connected = IncomingConnected;
+    type Start = Start;
+    type Events = IncomingEvents;
+    type End = IncomingEnd;
+    type Reply = String;
+
+    fn on_connected(&mut self, _msg: IncomingConnected) -> String {
+        println!("Got connected message, sending join.");
+        let join_msg = JoinMessage { event_type: JOIN.to_string(), team_name: self.options.team_name.clone() };
+        return serde_json::to_string(&join_msg).expect("Could not serialize join message.");
+    }
+
+    fn on_start(&mut self, msg: Start) {
+        println!("Game started!");
+        // The names were checked when the options were read
+        let strategies = Composer::from_names(&self.options.strategies).unwrap();
+        let seed = self.options.seed.unwrap_or(0);
+        println!("Playing with seed {}.", seed);
+        let mut ai = Ai::new(&msg, Logger::new(&self.options.log_dir, self.options.verbosity), seed);
+        ai.set_strategies(strategies);
+        self.ai = Some(ai);
+    }
+
+    // We lost the connection and joined again, so start over with what the server tells us now
+    fn on_resume(&mut self, msg: &IncomingEvents) {
+        println!("Resuming the game at round {}.", msg.round_id);
+        let start = Start { event_type: START.to_string(), config: msg.config, you: msg.you.clone(), other_teams: msg.other_teams.clone() };
+        self.on_start(start);
+    }
+
+    fn on_round(&mut self, msg: IncomingEvents) -> String {
+        println!("Got som events!");
+        self.rounds = msg.round_id + 1;
+        let ai = self.ai.as_mut().expect("Got events before the Ai was made.");
+        let actions = ai.handle_message(msg);
+        return serde_json::to_string(&actions).expect("Could not serialize actions message.");
+    }
+
+    fn on_end(&mut self, msg: IncomingEnd) {
+        println!("Game ended!");
+        let outcome = if msg.you.team_id == msg.winner_team_id {
+            println!("WE WON!!!");
+            Outcome::Won
+        } else if msg.winner_team_id == TIE_TEAM_ID {
+            println!("IT'S A TIE!!!");
+            Outcome::Tie
+        } else {
+            println!("WE DIDN'T WIN!!!");
+            Outcome::Lost
+        };
+        self.result = Some(GameResult {
+            outcome: outcome,
+            rounds: self.rounds,
+            bots_alive: msg.you.bots.iter().filter(|bot| bot.alive).count(),
+        });
+    }
+}
+
+// Parses a text message for the automaton
+pub fn parse_incoming(pl: &str) -> SerenityResult<Incoming<Game>> {
+    let message_json: IncomingMessage = try!(serde_json::from_str(pl));
+    let incoming = match message_json.event_type.as_ref() {
+        CONNECTED => Incoming::Connected(try!(serde_json::from_str(pl))),
+        START => Incoming::Start(try!(serde_json::from_str(pl))),
+        EVENTS => Incoming::Events(try!(serde_json::from_str(pl))),
+        END => Incoming::End(try!(serde_json::from_str(pl))),
+        ERROR => {
+            let error: IncomingError = try!(serde_json::from_str(pl));
+            return Err(SerenityError::Refused(error.data));
+        }
+        _ => return Err(SerenityError::UnknownMessage(message_json.event_type)),
+    };
+    return Ok(incoming);
+}
+
+#[cfg(test)]
+use sim::{default_config, Simulator};
+#[cfg(test)]
+use defs::{ActionsMessage, EchoEvent, Event, HitEvent};
+#[cfg(test)]
+use position::Pos;
+
+// The events message team 0 gets for the next round of the simulated game
+#[cfg(test)]
+fn next_round(sim: &mut Simulator) -> IncomingEvents {
+    sim.play_round(&vec![]).into_iter().find(|&(team_id, _)| team_id == 0).unwrap().1
+}
+
+#[cfg(test)]
+fn two_team_sim() -> Simulator {
+    Simulator::new(default_config(), &vec!["Serenity".to_string(), "Reavers".to_string()], 1)
+}
+
+#[test]
+fn malformed_events_do_not_cost_the_round() {
+    let options = Options { verbosity: 0, ..Options::defaults() };
+    let mut sim = two_team_sim();
+    let mut game = Game::new(&options);
+    game.on_start(sim.start_message(0));
+
+    let msg = next_round(&mut sim);
+    // A hit without a source, and an event the server doesn't have
+    let text = serde_json::to_string(&msg).unwrap()
+        .replace(r#""events":["#, r#""events":[{"event":"hit","botId":3},{"event":"teleport","botId":0},"#);
+
+    let reply = match parse_incoming(&text) {
+        Ok(Incoming::Events(msg)) => game.on_round(msg),
+        _ => panic!("Expected an events message"),
+    };
+    let actions: ActionsMessage = serde_json::from_str(&reply).unwrap();
+    assert_eq!(actions.round_id, 0);
+    assert!(!actions.actions.is_empty());
+}
+
+#[test]
+fn resumed_game_plays_on_from_the_round_it_got() {
+    let options = Options { verbosity: 0, ..Options::defaults() };
+    let mut sim = two_team_sim();
+    for _ in 0..4 {
+        next_round(&mut sim);
+    }
+
+    // We come back in round 4 with an Ai that has seen nothing of the game
+    let mut game = Game::new(&options);
+    for round_id in 4..8 {
+        let mut msg = next_round(&mut sim);
+        if round_id == 4 {
+            // Echoes and hits of what the Ai we lost sent
+            msg.events.push(Event::Echo(EchoEvent { pos: Pos::new(4, 4) }).into());
+            msg.events.push(Event::Hit(HitEvent { bot_id: 4, source: 0 }).into());
+        }
+        if round_id == 4 {
+            game.on_resume(&msg);
+        }
+        let actions: ActionsMessage = serde_json::from_str(&game.on_round(msg)).unwrap();
+        assert_eq!(actions.round_id, round_id);
+        assert!(!actions.actions.is_empty());
+    }
+}
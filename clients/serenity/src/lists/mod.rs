@@ -1,9 +1,9 @@
 use std::fmt;
-use defs:: { Action, Event, get_event_name };
+use defs:: { Action, ActionKind, Event, EventKind };
 use defs::Event::*;
 use position::Pos;
-use strings::{ NOACTION, ALL, RADARECHO, SEE, CANNON };
 use ai::bot::Bot;
+use ai::assign::Assignment;
 
 pub trait AsteroidList {
     fn register(&mut self, pos: Pos);
@@ -63,7 +63,7 @@ pub trait ActionsList {
     fn populate(bots: &Vec<Bot>) -> Vec<Action>;
     fn get_action(&self, id: i16) -> Option<&Action>;
     fn get_action_mut(&mut self, id: i16) -> Option<&mut Action>;
-    fn set_action_for(&mut self, id: i16, action: &str, pos: Pos);
+    fn set_action_for(&mut self, id: i16, kind: ActionKind, pos: Pos);
     fn render(&self) -> String;
 }
 
@@ -74,7 +74,7 @@ impl ActionsList for Vec<Action> {
         bots.iter()
             .map(|b| Action {
                 bot_id: b.id,
-                action_type: NOACTION.to_string(),
+                kind: ActionKind::Noaction,
                 pos: Pos {x: 0, y: 0},
             })
             .collect::<Vec<Action>>()
@@ -93,11 +93,11 @@ impl ActionsList for Vec<Action> {
     }
 
     #[allow(dead_code)]
-    fn set_action_for(&mut self, id: i16, action_type: &str, pos: Pos) {
+    fn set_action_for(&mut self, id: i16, kind: ActionKind, pos: Pos) {
         let opt_act = self.get_action_mut(id);
         debug_assert!(opt_act.is_some());
         if let Some(action) = opt_act {
-            action.action_type = action_type.to_string();
+            action.kind = kind;
             action.pos = pos;
         }
     }
@@ -122,14 +122,14 @@ pub trait HistoryList {
     fn get(&self, round_id: &i16) -> Option<&HistoryEntry>;
     fn get_mut(&mut self, round_id: i16) -> Option<&mut HistoryEntry>;
     fn filter_relevant(&self, events: &Vec<Event>) -> Vec<Event>;
-    fn get_events(&self, match_event: &str, since: i16) -> Vec<(Event, i16)>;
-    fn get_events_for_round(&self, match_event: &str, round_id: i16) -> Vec<Event>;
+    fn get_events(&self, match_event: EventKind, since: i16) -> Vec<(Event, i16)>;
+    fn get_events_for_round(&self, match_event: EventKind, round_id: i16) -> Vec<Event>;
     fn get_last_enemy_position(&self) -> Option<(Event, i16)>;
     fn get_last_attack_action(&self) -> Option<(Action, i16)>;
     fn get_echo_positions(&self, since: i16) -> Vec<(Pos,i16)>;
     fn get_unused_echoes(&self, since: i16) -> Vec<(Pos,i16)>;
-    fn get_actions(&self, match_action: &str, since: i16) -> Vec<(Action, i16)>;
-    fn get_actions_for_round(&self, match_action: &str, round_id: i16) -> Vec<Action>;
+    fn get_actions(&self, match_action: ActionKind, since: i16) -> Vec<(Action, i16)>;
+    fn get_actions_for_round(&self, match_action: ActionKind, round_id: i16) -> Vec<Action>;
     fn get_action_for_bot(&self, bot_id: &i16, round_id: &i16) -> Option<Action>;
     fn set_mode(&mut self, round_id: &i16, mode: ActionMode);
     fn get_mode(&self, round_id: i16) -> ActionMode;
@@ -137,10 +137,25 @@ pub trait HistoryList {
     fn get_decision(&self, round_id: i16) -> Decision;
 }
 
+// Entries are indexed by round_id. When we join a game that is already going (see
+// Game::on_resume), the rounds we missed get empty entries so that still holds.
+fn pad_history(history: &mut Vec<HistoryEntry>, round_id: i16) {
+    while (history.len() as i16) < round_id {
+        let missed = history.len() as i16;
+        history.push(HistoryEntry {
+            round_id: missed,
+            events: Vec::new(),
+            actions: Vec::new(),
+            decision: Decision::with_defaults(),
+        });
+    }
+}
+
 impl HistoryList for Vec<HistoryEntry> {
     #[allow(dead_code)]
     fn add_events(&mut self, round_id: i16, events: &Vec<Event>) {
-        debug_assert!(0 <= round_id && round_id <= self.len() as i16, "Adding either to existing round or to nextcoming one.");
+        debug_assert!(0 <= round_id, "Adding to a round before the game started.");
+        pad_history(self, round_id);
         let filtered_events = self.filter_relevant(events);
         if self.len() as i16 > round_id {
             if let Some(history_entry) = self.get_mut(round_id) {
@@ -158,7 +173,8 @@ impl HistoryList for Vec<HistoryEntry> {
 
     #[allow(dead_code)]
     fn add_actions(&mut self, round_id: i16, actions: &Vec<Action>) {
-        debug_assert!(0 <= round_id && round_id <= self.len() as i16, "Adding either to existing round or to nextcoming one.");
+        debug_assert!(0 <= round_id, "Adding to a round before the game started.");
+        pad_history(self, round_id);
         let a = actions.iter().cloned().collect();
         if self.len() as i16 > round_id {
             if let Some(history_entry) = self.get_mut(round_id) {
@@ -194,7 +210,6 @@ impl HistoryList for Vec<HistoryEntry> {
             .cloned()
             .filter(|e| {match *e {
                     Noaction(_) => false,
-                    Invalid => false,
                     _ => true,
                 }})
             .collect()
@@ -203,7 +218,7 @@ impl HistoryList for Vec<HistoryEntry> {
     // Returns each matching event as a tuple with round_id as second value
     // Pass 1 for since if you want the current round.
     #[allow(dead_code,unused_variables)]
-    fn get_events(&self, match_event: &str, since: i16) -> Vec<(Event, i16)> {
+    fn get_events(&self, match_event: EventKind, since: i16) -> Vec<(Event, i16)> {
         debug_assert!(since >= 0);
         let last_round = self.len() as i16 - 1;
         self.iter()
@@ -219,20 +234,20 @@ impl HistoryList for Vec<HistoryEntry> {
                 .iter()
                 .cloned()
                 .zip(round_ids)
-                .filter(|e| get_event_name(&e.0) == match_event)
+                .filter(|e| e.0.kind() == match_event)
             })
             .collect()
     }
 
     #[allow(dead_code)]
-    fn get_events_for_round(&self, match_event: &str, round_id: i16) -> Vec<Event> {
+    fn get_events_for_round(&self, match_event: EventKind, round_id: i16) -> Vec<Event> {
         debug_assert!(0 <= round_id && round_id < self.len() as i16);
         self.iter()
             .filter(|he| he.round_id == round_id)
             .flat_map(|he| he.events
                 .iter()
                 .cloned()
-                .filter(|e| get_event_name(&e) == match_event))
+                .filter(|e| e.kind() == match_event))
             .collect()
     }
 
@@ -241,8 +256,8 @@ impl HistoryList for Vec<HistoryEntry> {
         let last_entry = &self[self.len()-1];
         let mut round = last_entry.round_id + 0;
         while round > -1 {
-            let mut see_events = self.get_events_for_round( RADARECHO, round );
-            see_events.append(&mut self.get_events_for_round( SEE, round ));
+            let mut see_events = self.get_events_for_round( EventKind::Echo, round );
+            see_events.append(&mut self.get_events_for_round( EventKind::See, round ));
             for event in see_events {
                 return Some( (event, round) );
             }
@@ -256,7 +271,7 @@ impl HistoryList for Vec<HistoryEntry> {
         let last_entry = &self[self.len()-1];
         let mut round = last_entry.round_id + 0;
         while round > -1 {
-            let cannon_actions = self.get_actions_for_round( CANNON, round );
+            let cannon_actions = self.get_actions_for_round( ActionKind::Cannon, round );
             for action in cannon_actions {
                 return Some( (action, round) );
             }
@@ -271,8 +286,8 @@ impl HistoryList for Vec<HistoryEntry> {
     fn get_echo_positions(&self, since: i16) -> Vec<(Pos,i16)> {
         debug_assert!(since >= 0);
         // get all echo positions
-        let mut see_events = self.get_events( SEE, since );
-        see_events.append(&mut self.get_events( RADARECHO, since ));
+        let mut see_events = self.get_events( EventKind::See, since );
+        see_events.append(&mut self.get_events( EventKind::Echo, since ));
         see_events.sort_by(|a, b| a.1.cmp(&b.1));
 
         see_events
@@ -326,7 +341,7 @@ impl HistoryList for Vec<HistoryEntry> {
 
     // Returns each matching action as a tuple with round_id as second value
     #[allow(dead_code,unused_variables)]
-    fn get_actions(&self, match_action: &str, since: i16) -> Vec<(Action, i16)> {
+    fn get_actions(&self, match_action: ActionKind, since: i16) -> Vec<(Action, i16)> {
         debug_assert!(since >= 0);
         let last_round = self.len() as i16 - 1;
         self.iter()
@@ -340,26 +355,20 @@ impl HistoryList for Vec<HistoryEntry> {
                 he.actions.iter()
                     .cloned()
                     .zip(round_ids)
-                    .filter(|e| e.0.action_type == match_action.to_string())
+                    .filter(|e| e.0.kind == match_action)
             })
             .collect()
     }
 
     #[allow(dead_code)]
-    fn get_actions_for_round(&self, match_action: &str, round_id: i16) -> Vec<Action> {
+    fn get_actions_for_round(&self, match_action: ActionKind, round_id: i16) -> Vec<Action> {
         debug_assert!(0 <= round_id && round_id < self.len() as i16);
         self.iter()
             .filter(|he| he.round_id == round_id)
             .flat_map(|he| he.actions
                 .iter()
                 .cloned()
-                .filter(|e| {
-                    if match_action == ALL {
-                        true
-                    } else {
-                        e.action_type == match_action.to_string()
-                    }
-                }))
+                .filter(|e| e.kind == match_action))
             .collect()
     }
 
@@ -367,8 +376,9 @@ impl HistoryList for Vec<HistoryEntry> {
     fn get_action_for_bot(&self, bot_id: &i16, round_id: &i16) -> Option<Action> {
         debug_assert!(0 <= *round_id && *round_id < self.len() as i16);
         debug_assert!(0 <= *bot_id);
-        self.get_actions_for_round( ALL, *round_id )
-            .iter()
+        self.iter()
+            .filter(|he| he.round_id == *round_id)
+            .flat_map(|he| he.actions.iter())
             .cloned()
             .find(|ac| ac.bot_id == *bot_id)
     }
@@ -420,6 +430,8 @@ pub struct Decision {
     pub target: Option<Pos>,
     // Echoes we got this round, but did not act on
     pub unused_echoes: Vec<Pos>,
+    // Who did what and why, when we split the bots between several targets
+    pub assignments: Vec<Assignment>,
 }
 
 impl Decision {
@@ -428,6 +440,7 @@ impl Decision {
             mode: ActionMode::Nomode,
             target: None,
             unused_echoes: Vec::new(),
+            assignments: Vec::new(),
         }
     }
 
@@ -440,6 +453,14 @@ impl Decision {
             .cloned()
             .collect();
     }
+
+    // The target is the one we spent the most on first, the ignored ones are kept as unused
+    pub fn add_assignment_decision(&mut self, assignments: Vec<Assignment>, ignored: Vec<Pos>) {
+        self.mode = ActionMode::Attack;
+        self.target = assignments.first().map(|assignment| assignment.target);
+        self.unused_echoes = ignored;
+        self.assignments = assignments;
+    }
 }
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
@@ -1,18 +1,40 @@
 extern crate time;
 
 use std::io::prelude::*;
-use std::fs::File;
+use std::fs::{self, File};
+use std::path::Path;
 
 pub struct Logger {
     file: Option<File>,
+    // Messages indented this deep or deeper are not written
+    verbosity: usize,
 }
 
 static TAB: &'static str = "    ";
 
 impl Logger {
-    pub fn new() -> Logger {
+    // Creates a new log file in dir. Verbosity 0 doesn't create a file at all.
+    pub fn new(dir: &str, verbosity: usize) -> Logger {
+        if verbosity == 0 {
+            return Logger::disabled();
+        }
+        let _ = fs::create_dir_all(dir);
+        let path = Path::new(dir).join(Logger::make_filename());
+        let file = File::create(&path);
+        if let Err(ref e) = file {
+            println!("Could not create log file {}: {}", path.display(), e);
+        }
         return Logger {
-            file: File::create(Logger::make_filename()).ok(),
+            file: file.ok(),
+            verbosity: verbosity,
+        }
+    }
+
+    // A logger that throws everything away
+    pub fn disabled() -> Logger {
+        return Logger {
+            file: None,
+            verbosity: 0,
         }
     }
 
@@ -23,6 +45,9 @@ impl Logger {
     }
 
     pub fn log(&mut self, msg: &str, indent: usize) {
+        if indent >= self.verbosity {
+            return;
+        }
         if let Some(ref mut f) = self.file {
             for _ in 0..indent {
                 let _ = f.write_all(TAB.as_bytes());
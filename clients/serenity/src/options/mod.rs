@@ -0,0 +1,304 @@
+use std::env;
+use std::fs::File;
+use std::io::Read;
+use std::str::FromStr;
+use std::{i64, u16, u32, usize};
+use docopt::Docopt;
+use toml;
+use ai::strategy::DEFAULT_STRATEGIES;
+
+static USAGE: &'static str = "
+serenity - a Space Tyckiting bot
+
+Usage: serenity [options]
+       serenity replay <gamelog> --team <id> [options]
+       serenity playback <recording> [options]
+       serenity tournament <player>... [--matches <count>] [options]
+       serenity (-h | --help)
+
+The replay command plays a game log from the server again as one of its teams, and
+shows where our actions now differ from the ones sent in the game. The playback command
+plays the games of a --record file again and shows where what we send has changed.
+The tournament command plays matches between the players on the offline simulator, with
+seeds counting up from --seed, and shows how each did. The players are serenity, which
+plays with the strategies option, serenity:<list> with the listed strategies, and the
+scripted random, sniper and idle. random is not clients/rust's RandomAi itself, that one
+lives in a binary we can't link to, but a copy of it that draws from the seeded generator.
+
+Options:
+  -h, --help                Show this help message
+  -t, --team <id>           Team to replay the game log as
+  -m, --matches <count>     Matches to play in the tournament [default: 100]
+  -H, --host <host>         Host to connect to [env: SERENITY_HOST]
+  -P, --port <port>         Port to connect to [env: SERENITY_PORT]
+  -n, --name <name>         Team's name [env: SERENITY_NAME]
+  -s, --strategies <list>   Comma separated strategies to run, in order [env: SERENITY_STRATEGIES]
+  -l, --log-dir <dir>       Directory to write log files to [env: SERENITY_LOG_DIR]
+  --seed <seed>             Seed for every random choice, printed at start if picked for us [env: SERENITY_SEED]
+  -v, --verbosity <level>   How much to log, 0 for nothing [env: SERENITY_VERBOSITY]
+  -g, --games <count>       Games to play one after another, 0 to keep playing [env: SERENITY_GAMES]
+  --requeue-delay <ms>      Wait between games, e.g. for the server to restart [env: SERENITY_REQUEUE_DELAY]
+  --reconnects <count>      Times to try connecting again if we lose a game's connection [env: SERENITY_RECONNECTS]
+  -r, --record <file>       Append every message to and from the server to the file [env: SERENITY_RECORD]
+  -c, --config <file>       TOML file with any of the above, e.g. host = \"localhost\" [env: SERENITY_CONFIG]
+
+Command line options win over environment variables, which win over the config file.
+";
+
+const DEFAULT_HOST: &'static str = "localhost";
+const DEFAULT_PORT: u16 = 3000;
+const DEFAULT_NAME: &'static str = "Serenity";
+const DEFAULT_LOG_DIR: &'static str = ".";
+// Logs everything our Logger has, three levels of indentation
+const DEFAULT_VERBOSITY: usize = 3;
+const DEFAULT_GAMES: u32 = 1;
+const DEFAULT_REQUEUE_DELAY: u64 = 1000;
+const DEFAULT_RECONNECTS: u32 = 5;
+
+#[derive(RustcDecodable, Debug)]
+struct Args {
+    cmd_replay: bool,
+    arg_gamelog: Option<String>,
+    flag_team: Option<i16>,
+    cmd_playback: bool,
+    arg_recording: Option<String>,
+    cmd_tournament: bool,
+    arg_player: Vec<String>,
+    flag_matches: u32,
+    flag_host: Option<String>,
+    flag_port: Option<u16>,
+    flag_name: Option<String>,
+    flag_strategies: Option<String>,
+    flag_log_dir: Option<String>,
+    flag_seed: Option<usize>,
+    flag_verbosity: Option<usize>,
+    flag_games: Option<u32>,
+    flag_requeue_delay: Option<u64>,
+    flag_reconnects: Option<u32>,
+    flag_record: Option<String>,
+    flag_config: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    // Play games on the server
+    Play,
+    // Replay a game log file as the team with the id
+    Replay(String, i16),
+    // Play the games of a recording again
+    Playback(String),
+    // Play this many matches between the players offline
+    Tournament(Vec<String>, u32),
+}
+
+#[derive(Debug, Clone)]
+pub struct Options {
+    pub command: Command,
+    pub host: String,
+    pub port: u16,
+    pub team_name: String,
+    pub strategies: String,
+    pub log_dir: String,
+    pub seed: Option<usize>,
+    pub verbosity: usize,
+    // 0 for no limit
+    pub games: u32,
+    // Milliseconds
+    pub requeue_delay: u64,
+    pub reconnects: u32,
+    pub record: Option<String>,
+}
+
+// Options from one source, anything not given there is None
+#[derive(Debug, Clone, Default)]
+struct PartialOptions {
+    host: Option<String>,
+    port: Option<u16>,
+    team_name: Option<String>,
+    strategies: Option<String>,
+    log_dir: Option<String>,
+    seed: Option<usize>,
+    verbosity: Option<usize>,
+    games: Option<u32>,
+    requeue_delay: Option<u64>,
+    reconnects: Option<u32>,
+    record: Option<String>,
+}
+
+impl Options {
+    // Reads the command line, environment and config file. Exits with usage on bad arguments.
+    pub fn from_args() -> Options {
+        let args: Args = Docopt::new(USAGE).and_then(|d| d.decode()).unwrap_or_else(|e| e.exit());
+        let config_file = args.flag_config.clone().or(env::var("SERENITY_CONFIG").ok());
+        let command = match (args.cmd_replay, args.arg_gamelog.clone(), args.flag_team, args.arg_recording.clone()) {
+            (true, Some(path), Some(team_id), _) => Command::Replay(path, team_id),
+            (false, _, _, Some(ref path)) if args.cmd_playback => Command::Playback(path.clone()),
+            _ if args.cmd_tournament => Command::Tournament(args.arg_player.clone(), args.flag_matches),
+            _ => Command::Play,
+        };
+
+        let from_file = match config_file {
+            Some(path) => PartialOptions::from_toml(&read_file(&path))
+                .unwrap_or_else(|e| panic!("Could not parse config file {}: {}", path, e)),
+            None => PartialOptions::default(),
+        };
+
+        let options = PartialOptions::from_args(args)
+            .or(PartialOptions::from_env())
+            .or(from_file)
+            .finish();
+        Options { command: command, ..options }
+    }
+
+    // Every option at its default, e.g. for tests
+    #[allow(dead_code)]
+    pub fn defaults() -> Options {
+        PartialOptions::default().finish()
+    }
+}
+
+impl PartialOptions {
+    fn from_args(args: Args) -> PartialOptions {
+        PartialOptions {
+            host: args.flag_host,
+            port: args.flag_port,
+            team_name: args.flag_name,
+            strategies: args.flag_strategies,
+            log_dir: args.flag_log_dir,
+            seed: args.flag_seed,
+            verbosity: args.flag_verbosity,
+            games: args.flag_games,
+            requeue_delay: args.flag_requeue_delay,
+            reconnects: args.flag_reconnects,
+            record: args.flag_record,
+        }
+    }
+
+    fn from_env() -> PartialOptions {
+        PartialOptions {
+            host: env::var("SERENITY_HOST").ok(),
+            port: parse_env("SERENITY_PORT"),
+            team_name: env::var("SERENITY_NAME").ok(),
+            strategies: env::var("SERENITY_STRATEGIES").ok(),
+            log_dir: env::var("SERENITY_LOG_DIR").ok(),
+            seed: parse_env("SERENITY_SEED"),
+            verbosity: parse_env("SERENITY_VERBOSITY"),
+            games: parse_env("SERENITY_GAMES"),
+            requeue_delay: parse_env("SERENITY_REQUEUE_DELAY"),
+            reconnects: parse_env("SERENITY_RECONNECTS"),
+            record: env::var("SERENITY_RECORD").ok(),
+        }
+    }
+
+    fn from_toml(contents: &str) -> Result<PartialOptions, String> {
+        let mut parser = toml::Parser::new(contents);
+        let table = match parser.parse() {
+            Some(table) => table,
+            None => return Err(format!("{:?}", parser.errors)),
+        };
+
+        let string = |key: &str| table.get(key).and_then(|v| v.as_str()).map(|s| s.to_string());
+        let integer = |key: &str| table.get(key).and_then(|v| v.as_integer());
+
+        // Anything else would wrap around to some other value, e.g. -1 games to 4294967295
+        let ranged = |key: &str, min: i64, max: i64| match integer(key) {
+            Some(i) if i < min || i > max => Err(format!("{} {} is not between {} and {}", key, i, min, max)),
+            i => Ok(i),
+        };
+        let usize_max = if usize::MAX as u64 > i64::MAX as u64 { i64::MAX } else { usize::MAX as i64 };
+
+        Ok(PartialOptions {
+            host: string("host"),
+            port: try!(ranged("port", 1, u16::MAX as i64)).map(|i| i as u16),
+            team_name: string("name"),
+            strategies: string("strategies"),
+            log_dir: string("log_dir"),
+            seed: try!(ranged("seed", 0, usize_max)).map(|i| i as usize),
+            verbosity: try!(ranged("verbosity", 0, usize_max)).map(|i| i as usize),
+            games: try!(ranged("games", 0, u32::MAX as i64)).map(|i| i as u32),
+            requeue_delay: try!(ranged("requeue_delay", 0, i64::MAX)).map(|i| i as u64),
+            reconnects: try!(ranged("reconnects", 0, u32::MAX as i64)).map(|i| i as u32),
+            record: string("record"),
+        })
+    }
+
+    // Fills in whatever we don't have from other
+    fn or(self, other: PartialOptions) -> PartialOptions {
+        PartialOptions {
+            host: self.host.or(other.host),
+            port: self.port.or(other.port),
+            team_name: self.team_name.or(other.team_name),
+            strategies: self.strategies.or(other.strategies),
+            log_dir: self.log_dir.or(other.log_dir),
+            seed: self.seed.or(other.seed),
+            verbosity: self.verbosity.or(other.verbosity),
+            games: self.games.or(other.games),
+            requeue_delay: self.requeue_delay.or(other.requeue_delay),
+            reconnects: self.reconnects.or(other.reconnects),
+            record: self.record.or(other.record),
+        }
+    }
+
+    fn finish(self) -> Options {
+        Options {
+            command: Command::Play,
+            host: self.host.unwrap_or(DEFAULT_HOST.to_string()),
+            port: self.port.unwrap_or(DEFAULT_PORT),
+            team_name: self.team_name.unwrap_or(DEFAULT_NAME.to_string()),
+            strategies: self.strategies.unwrap_or(DEFAULT_STRATEGIES.to_string()),
+            log_dir: self.log_dir.unwrap_or(DEFAULT_LOG_DIR.to_string()),
+            seed: self.seed,
+            verbosity: self.verbosity.unwrap_or(DEFAULT_VERBOSITY),
+            games: self.games.unwrap_or(DEFAULT_GAMES),
+            requeue_delay: self.requeue_delay.unwrap_or(DEFAULT_REQUEUE_DELAY),
+            reconnects: self.reconnects.unwrap_or(DEFAULT_RECONNECTS),
+            record: self.record,
+        }
+    }
+}
+
+fn parse_env<T: FromStr>(name: &str) -> Option<T> {
+    match env::var(name) {
+        Ok(value) => match value.parse() {
+            Ok(parsed) => Some(parsed),
+            Err(_) => {
+                println!("Ignoring {}, could not parse {:?}", name, value);
+                None
+            }
+        },
+        Err(_) => None,
+    }
+}
+
+fn read_file(path: &str) -> String {
+    let mut contents = String::new();
+    File::open(path)
+        .and_then(|mut f| f.read_to_string(&mut contents))
+        .unwrap_or_else(|e| panic!("Could not read config file {}: {}", path, e));
+    contents
+}
+
+#[test]
+fn options_command_line_wins_over_config_file() {
+    let from_file = PartialOptions::from_toml("host = \"tournament\"\nport = 4000\nname = \"Reavers\"").unwrap();
+    let from_args = PartialOptions { host: Some("localhost".to_string()), ..PartialOptions::default() };
+    let options = from_args.or(PartialOptions::default()).or(from_file).finish();
+
+    assert_eq!(options.host, "localhost");
+    assert_eq!(options.port, 4000);
+    assert_eq!(options.team_name, "Reavers");
+    assert_eq!(options.strategies, DEFAULT_STRATEGIES);
+    assert_eq!(options.seed, None);
+}
+
+#[test]
+fn options_reject_broken_config_file() {
+    assert!(PartialOptions::from_toml("host = ").is_err());
+    assert!(PartialOptions::from_toml("port = 70000").is_err());
+    assert!(PartialOptions::from_toml("port = -1").is_err());
+    assert_eq!(PartialOptions::from_toml("port = 65535").unwrap().port, Some(65535));
+    assert!(PartialOptions::from_toml("games = -1").is_err());
+    assert!(PartialOptions::from_toml("requeue_delay = -500").is_err());
+    assert!(PartialOptions::from_toml("reconnects = 4294967296").is_err());
+    assert_eq!(PartialOptions::from_toml("games = 0").unwrap().games, Some(0));
+}
@@ -1,20 +1,20 @@
 use position::Pos;
-use rand;
 use rand::Rng;
+use util;
 use std::fmt;
 
 // Abstraction for the attacking methods to use
 // They pass in the number of available bots and this method will use the
 // right spread strategy for that number and return a vector
-pub fn smart_attack_spread(pos: Pos, available_bots: i16, map_radius: i16) -> Vec<Pos> {
+pub fn smart_attack_spread<R: Rng>(pos: Pos, available_bots: i16, map_radius: i16, rng: &mut R) -> Vec<Pos> {
     let mut shoot_at: Vec<Pos> = Vec::new();
 
     match available_bots {
         4 => {
-            shoot_at = triangle_smart(pos);
+            shoot_at = triangle_smart(pos, rng);
             shoot_at.push(pos);
         },
-        3 => shoot_at = triangle_smart(pos),
+        3 => shoot_at = triangle_smart(pos, rng),
         2 => {
             //TODO: Choose twin based on pos in map.
             let or: Orientation = *wall_orientation(pos).first().expect("Wall_or... should always return at least one value");
@@ -28,22 +28,29 @@ pub fn smart_attack_spread(pos: Pos, available_bots: i16, map_radius: i16) -> Ve
                 }).collect();
         }
         1 => {
-            shoot_at.push(pos.random_spread());
+            shoot_at.push(random_spread(pos, rng));
         },
         _ => ()
     }
     shoot_at
 }
 
+// One of the hexes around pos, or pos itself
+fn random_spread<R: Rng>(pos: Pos, rng: &mut R) -> Pos {
+    Pos {
+        x: pos.x + util::get_rand_range(rng, -1, 1),
+        y: pos.y + util::get_rand_range(rng, -1, 1)
+    }
+}
+
 fn at_edge(pos: Pos, map_radius: i16) -> bool {
     pos.distance(Pos::origo()) >= map_radius
 }
 
-pub fn triangle_smart(pos: Pos) -> Vec<Pos> {
-    let mut triangle = triangle_rand_tight(pos);
+pub fn triangle_smart<R: Rng>(pos: Pos, rng: &mut R) -> Vec<Pos> {
+    let mut triangle = triangle_rand_tight(pos, rng);
 
     // Shuffle so that the same will not be middled every time
-    let mut rng = rand::thread_rng();
     rng.shuffle(&mut triangle[..]);
     {
         let p: &mut Pos = triangle.first_mut().expect("There should be three points here!");
@@ -55,8 +62,8 @@ pub fn triangle_smart(pos: Pos) -> Vec<Pos> {
     return triangle;
 }
 
-pub fn triangle_rand_tight(pos: Pos) -> Vec<Pos> {
-    match rand::thread_rng().gen_range(0, 2) {
+pub fn triangle_rand_tight<R: Rng>(pos: Pos, rng: &mut R) -> Vec<Pos> {
+    match rng.gen_range(0, 2) {
         0 => { triangle_left(pos) }
         _ => { triangle_right(pos) }
     }
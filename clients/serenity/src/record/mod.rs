@@ -0,0 +1,233 @@
+extern crate serde;
+extern crate serde_json;
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::time::Instant;
+use automaton::Automaton;
+use error::SerenityResult;
+use game::Game;
+use options::Options;
+use session::{self, Stats};
+use transport::Transport;
+
+include!(concat!(env!("OUT_DIR"), "/record.rs"));
+
+pub const IN: &'static str = "in";
+pub const OUT: &'static str = "out";
+pub const CONNECT: &'static str = "connect";
+
+// Writes every message to and from the server to a file, one JSON frame per line, so a game
+// can be looked at afterwards or played back with Playback. A session's games and reconnects
+// all go to the same file, each connection starts with a CONNECT frame.
+pub struct Recorder {
+    file: File,
+    start: Instant,
+}
+
+impl Recorder {
+    pub fn create(path: &str) -> SerenityResult<Recorder> {
+        let file = try!(OpenOptions::new().create(true).append(true).open(path));
+        println!("Recording to {}", path);
+        Ok(Recorder { file: file, start: Instant::now() })
+    }
+
+    pub fn connected(&mut self) -> SerenityResult<()> {
+        self.write(CONNECT, "")
+    }
+
+    fn write(&mut self, direction: &str, payload: &str) -> SerenityResult<()> {
+        let elapsed = self.start.elapsed();
+        let frame = Frame {
+            time: elapsed.as_secs() * 1000 + elapsed.subsec_nanos() as u64 / 1000000,
+            direction: direction.to_string(),
+            round_id: round_of(payload),
+            payload: payload.to_string(),
+        };
+        let line = try!(serde_json::to_string(&frame));
+        try!(writeln!(self.file, "{}", line));
+        Ok(())
+    }
+}
+
+fn round_of(payload: &str) -> Option<i16> {
+    serde_json::from_str::<RoundOf>(payload).ok().and_then(|round| round.round_id)
+}
+
+// A transport that records everything that goes through it
+pub struct Recording<'a, T: Transport> {
+    inner: T,
+    recorder: &'a mut Recorder,
+}
+
+impl<'a, T: Transport> Recording<'a, T> {
+    pub fn new(inner: T, recorder: &'a mut Recorder) -> Recording<'a, T> {
+        Recording { inner: inner, recorder: recorder }
+    }
+}
+
+impl<'a, T: Transport> Transport for Recording<'a, T> {
+    fn receive(&mut self) -> SerenityResult<Option<String>> {
+        let received = try!(self.inner.receive());
+        if let Some(ref text) = received {
+            try!(self.recorder.write(IN, text));
+        }
+        Ok(received)
+    }
+
+    fn send(&mut self, text: &str) -> SerenityResult<()> {
+        try!(self.recorder.write(OUT, text));
+        self.inner.send(text)
+    }
+
+    fn close(&mut self) {
+        self.inner.close();
+    }
+}
+
+// A message we sent differently than in the recording. None if it wasn't sent at all.
+#[derive(Debug, Clone)]
+pub struct Difference {
+    pub round_id: Option<i16>,
+    pub recorded: Option<String>,
+    pub sent: Option<String>,
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let round = match self.round_id {
+            Some(round_id) => format!("Round {}", round_id),
+            None => "Outside rounds".to_string(),
+        };
+        let describe = |message: &Option<String>| message.clone().unwrap_or("nothing".to_string());
+        write!(f, "{}: recorded {}, sent {}", round, describe(&self.recorded), describe(&self.sent))
+    }
+}
+
+// A transport that plays the server's side of a recording and checks what we send against
+// what was sent then. The messages come as fast as we take them, the times aren't kept.
+pub struct Playback {
+    frames: VecDeque<Frame>,
+    differences: Vec<Difference>,
+}
+
+impl Playback {
+    pub fn load(path: &str) -> SerenityResult<Playback> {
+        let mut contents = String::new();
+        try!(File::open(path).and_then(|mut f| f.read_to_string(&mut contents)));
+        Playback::parse(&contents)
+    }
+
+    pub fn parse(contents: &str) -> SerenityResult<Playback> {
+        let mut frames = VecDeque::new();
+        for line in contents.lines().filter(|line| !line.trim().is_empty()) {
+            let frame: Frame = try!(serde_json::from_str(line));
+            frames.push_back(frame);
+        }
+        Ok(Playback { frames: frames, differences: Vec::new() })
+    }
+
+    // Moves on to the next connection of the recording, false if there are none left
+    pub fn next_connection(&mut self) -> bool {
+        while let Some(frame) = self.frames.pop_front() {
+            if frame.direction == CONNECT {
+                return true;
+            }
+            self.unanswered(frame);
+        }
+        false
+    }
+
+    pub fn differences(&self) -> &Vec<Difference> {
+        &self.differences
+    }
+
+    // Something was sent in the recording that we didn't send now
+    fn unanswered(&mut self, frame: Frame) {
+        if frame.direction == OUT {
+            self.differences.push(Difference { round_id: frame.round_id, recorded: Some(frame.payload), sent: None });
+        }
+    }
+}
+
+impl Transport for Playback {
+    fn receive(&mut self) -> SerenityResult<Option<String>> {
+        loop {
+            match self.frames.front().map(|frame| frame.direction.clone()) {
+                None => return Ok(None),
+                // The connection ended here
+                Some(ref direction) if direction == CONNECT => return Ok(None),
+                Some(ref direction) if direction == IN => return Ok(self.frames.pop_front().map(|frame| frame.payload)),
+                Some(_) => {
+                    let frame = self.frames.pop_front().unwrap();
+                    self.unanswered(frame);
+                }
+            }
+        }
+    }
+
+    fn send(&mut self, text: &str) -> SerenityResult<()> {
+        let recorded = match self.frames.front() {
+            Some(frame) if frame.direction == OUT => Some(frame.payload.clone()),
+            _ => None,
+        };
+        if recorded.is_some() {
+            self.frames.pop_front();
+        }
+        if recorded.as_ref().map(|payload| payload.as_ref()) != Some(text) {
+            self.differences.push(Difference { round_id: round_of(text), recorded: recorded, sent: Some(text.to_string()) });
+        }
+        Ok(())
+    }
+}
+
+// Plays every game of a recording as if it came from the server. The Ai plays each game again,
+// so any difference in what we send shows where it now does something else.
+pub fn play_back(path: &str, options: &Options) -> SerenityResult<(Stats, Vec<Difference>)> {
+    let playback = try!(Playback::load(path));
+    play_recording(playback, options)
+}
+
+fn play_recording(mut playback: Playback, options: &Options) -> SerenityResult<(Stats, Vec<Difference>)> {
+    let mut stats = Stats::new();
+
+    while playback.next_connection() {
+        let mut automaton = Automaton::new(Game::new(options));
+        automaton.connect();
+        loop {
+            try!(session::play_transport(&mut automaton, &mut playback));
+            if automaton.is_finished() || !playback.next_connection() {
+                break;
+            }
+            automaton.reconnect();
+        }
+        match automaton.hooks().result() {
+            Some(result) => stats.record(result),
+            None => stats.failures += 1,
+        }
+    }
+
+    Ok((stats, playback.differences().clone()))
+}
+
+#[test]
+fn playback_replays_a_reconnected_game() {
+    let playback = Playback::parse(include_str!("../../fixtures/reconnect.jsonl")).unwrap();
+    let options = Options { verbosity: 0, ..Options::defaults() };
+    let (stats, differences) = play_recording(playback, &options).unwrap();
+
+    // The second connection took the game over at round 4 and played it to the end
+    assert_eq!(stats.games, 1);
+    assert_eq!(stats.wins, 1);
+    assert_eq!(stats.rounds, 9);
+    assert!(differences.is_empty(), "{:?}", differences);
+
+    // Under another name our joins no longer match the recording
+    let playback = Playback::parse(include_str!("../../fixtures/reconnect.jsonl")).unwrap();
+    let options = Options { verbosity: 0, team_name: "Reavers".to_string(), ..Options::defaults() };
+    let (_, differences) = play_recording(playback, &options).unwrap();
+    assert_eq!(differences.len(), 2);
+    assert_eq!(differences[0].sent, Some("{\"type\":\"join\",\"teamName\":\"Reavers\"}".to_string()));
+}
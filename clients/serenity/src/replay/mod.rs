@@ -0,0 +1,335 @@
+extern crate serde;
+extern crate serde_json;
+
+use std::fmt;
+use std::fs::File;
+use std::io::Read;
+use ai::Ai;
+use ai::strategy::Composer;
+use defs::{Action, ActionKind, Bot, Config, Event, IncomingEvents, MaybeEvent, Start, Team};
+use error::{SerenityError, SerenityResult};
+use log::Logger;
+use options::Options;
+use position::Pos;
+use strings::{EVENTS, START};
+
+include!(concat!(env!("OUT_DIR"), "/replay.rs"));
+
+const ROUND: &'static str = "round";
+
+// A server game log (server/start-server.js --game-log-file) cut into what each team was sent.
+//
+// The log has every event of a round but not who it was sent to, so we work that out from
+// the rules: most events name the bot they're about, echoes go to the teams that radared
+// there and asteroid sightings to the teams with a bot close enough. If two teams radared
+// the same bot both get both echoes, the server would have sent one each.
+pub struct GameLog {
+    config: Config,
+    // As the game started
+    teams: Vec<Team>,
+    rounds: Vec<Round>,
+}
+
+struct Round {
+    round_id: i16,
+    // As the round ended
+    teams: Vec<Team>,
+    asteroids: Vec<Pos>,
+    events: Vec<Event>,
+    // The actions the server used in this round, sent in answer to the previous one
+    actions: Vec<Action>,
+}
+
+// An action we'd now choose differently than the one sent in the game
+#[derive(Debug, Clone)]
+pub struct Mismatch {
+    pub round_id: i16,
+    pub bot_id: i16,
+    pub sent: Option<Action>,
+    pub ours: Option<Action>,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let describe = |action: &Option<Action>| match *action {
+            Some(ref action) => action.to_string(),
+            None => "nothing".to_string(),
+        };
+        write!(f, "Round {} Bot {}: sent {}, now {}", self.round_id, self.bot_id, describe(&self.sent), describe(&self.ours))
+    }
+}
+
+pub struct ReplayResult {
+    pub rounds: usize,
+    // Actions compared, counting a bot that acted in only one of the games
+    pub actions: usize,
+    pub mismatches: Vec<Mismatch>,
+}
+
+impl ReplayResult {
+    pub fn render(&self) -> String {
+        format!("{} of {} actions match over {} rounds",
+                self.actions - self.mismatches.len(), self.actions, self.rounds)
+    }
+}
+
+fn require<T>(value: Option<T>, entry: &str, field: &'static str) -> SerenityResult<T> {
+    match value {
+        Some(value) => Ok(value),
+        None => Err(SerenityError::MissingField { entry: entry.to_string(), field: field }),
+    }
+}
+
+fn team_of(teams: &Vec<Team>, bot_id: i16) -> Option<i16> {
+    teams.iter()
+        .find(|team| team.bots.iter().any(|bot| bot.bot_id == bot_id))
+        .map(|team| team.team_id)
+}
+
+impl GameLog {
+    pub fn load(path: &str) -> SerenityResult<GameLog> {
+        let mut contents = String::new();
+        try!(File::open(path).and_then(|mut f| f.read_to_string(&mut contents)));
+        GameLog::parse(&contents)
+    }
+
+    pub fn parse(json: &str) -> SerenityResult<GameLog> {
+        let entries: Vec<LogEntry> = try!(serde_json::from_str(json));
+        let mut start: Option<(Config, Vec<Team>)> = None;
+        let mut rounds: Vec<Round> = Vec::new();
+
+        for entry in entries {
+            match entry.entry_type.as_ref() {
+                START => {
+                    start = Some((try!(require(entry.config, START, "config")), try!(require(entry.teams, START, "teams"))));
+                }
+                ROUND => {
+                    rounds.push(Round {
+                        round_id: try!(require(entry.round_id, ROUND, "roundId")),
+                        teams: try!(require(entry.teams, ROUND, "teams")),
+                        asteroids: entry.asteroids.unwrap_or(Vec::new()),
+                        // Events we can't make sense of didn't reach the Ai when the game was played either
+                        events: entry.events.unwrap_or(Vec::new()).into_iter().filter_map(|ev| ev.0.ok()).collect(),
+                        actions: entry.actions.unwrap_or(Vec::new()),
+                    });
+                }
+                _ => (),
+            }
+        }
+
+        match start {
+            Some((config, teams)) => Ok(GameLog { config: config, teams: teams, rounds: rounds }),
+            None => Err(SerenityError::UnexpectedMessage { expected: START, got: "a game log without one".to_string() }),
+        }
+    }
+
+    pub fn rounds(&self) -> usize {
+        self.rounds.len()
+    }
+
+    pub fn start_message(&self, team_id: i16) -> Option<Start> {
+        team_view(&self.teams, team_id).map(|(you, other_teams)| {
+            Start { event_type: START.to_string(), config: self.config, you: you, other_teams: other_teams }
+        })
+    }
+
+    // What the team was sent after the round at index
+    pub fn events_message(&self, index: usize, team_id: i16) -> Option<IncomingEvents> {
+        let round = &self.rounds[index];
+        let (you, other_teams) = match team_view(&round.teams, team_id) {
+            Some(view) => view,
+            None => return None,
+        };
+
+        let mut own: Vec<Event> = Vec::new();
+        let mut all: Vec<Event> = Vec::new();
+        for ev in &round.events {
+            if let Event::Die(_) = *ev {
+                all.push(*ev);
+            } else if self.event_targets(round, ev).contains(&team_id) {
+                own.push(*ev);
+            }
+        }
+        // The server sends a team's own events first and then the ones for everybody
+        own.extend(all.into_iter());
+
+        Some(IncomingEvents {
+            event_type: EVENTS.to_string(),
+            round_id: round.round_id,
+            config: self.config,
+            you: you,
+            other_teams: other_teams,
+            events: own.into_iter().map(MaybeEvent::from).collect(),
+        })
+    }
+
+    // What the team sent in answer to the round at index, None for the last round
+    pub fn sent_actions(&self, index: usize, team_id: i16) -> Option<Vec<Action>> {
+        self.rounds.get(index + 1).map(|next| {
+            next.actions.iter()
+                .filter(|action| team_of(&next.teams, action.bot_id) == Some(team_id))
+                .cloned()
+                .collect()
+        })
+    }
+
+    // The teams an event was sent to, other than die which goes to everyone
+    fn event_targets(&self, round: &Round, ev: &Event) -> Vec<i16> {
+        let about = |bot_id: i16| team_of(&round.teams, bot_id).into_iter().collect();
+        match *ev {
+            Event::Noaction(ref ev) => about(ev.bot_id),
+            Event::Move(ref ev) => about(ev.bot_id),
+            Event::Damaged(ref ev) => about(ev.bot_id),
+            Event::Detected(ref ev) => about(ev.bot_id),
+            Event::Hit(ref ev) => about(ev.source),
+            Event::See(ref ev) => about(ev.source),
+            Event::Echo(ref ev) => {
+                let pos = ev.pos;
+                // Radars don't echo the radaring team's own bots
+                let owner = round.teams.iter()
+                    .find(|team| team.bots.iter().any(|bot| bot.pos == Some(pos)))
+                    .map(|team| team.team_id);
+                let mut targets: Vec<i16> = round.actions.iter()
+                    .filter(|action| action.kind == ActionKind::Radar && action.pos.distance(pos) <= self.config.radar)
+                    .filter_map(|action| team_of(&round.teams, action.bot_id))
+                    .filter(|team_id| Some(*team_id) != owner)
+                    .collect();
+                targets.sort();
+                targets.dedup();
+                targets
+            }
+            Event::SeeAsteroid(ref ev) => {
+                let pos = ev.pos;
+                round.teams.iter()
+                    .filter(|team| team.bots.iter().any(|bot| {
+                        bot.alive && bot.pos.map_or(false, |bot_pos| bot_pos.distance(pos) <= self.config.see)
+                    }))
+                    .map(|team| team.team_id)
+                    .collect()
+            }
+            Event::Die(_) => Vec::new(),
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn asteroids(&self) -> Vec<Pos> {
+        self.rounds.first().map(|round| round.asteroids.clone()).unwrap_or(Vec::new())
+    }
+}
+
+// The team as it sees itself, and the other teams as it sees them: every bot that's not ours
+// under each of them, without positions or hp.
+fn team_view(teams: &Vec<Team>, team_id: i16) -> Option<(Team, Vec<Team>)> {
+    let you = match teams.iter().find(|team| team.team_id == team_id) {
+        Some(team) => team.clone(),
+        None => return None,
+    };
+    let enemies: Vec<Bot> = teams.iter()
+        .filter(|team| team.team_id != team_id)
+        .flat_map(|team| team.bots.iter())
+        .map(|bot| Bot { pos: None, hp: None, ..bot.clone() })
+        .collect();
+    let other_teams = teams.iter()
+        .filter(|team| team.team_id != team_id)
+        .map(|team| Team { name: team.name.clone(), team_id: team.team_id, bots: enemies.clone() })
+        .collect();
+    Some((you, other_teams))
+}
+
+fn same_action(a: &Action, b: &Action) -> bool {
+    a.kind == b.kind && a.pos == b.pos
+}
+
+// Plays the logged game again as team_id and compares our actions to the ones sent in it.
+// The game goes on as logged whatever we do, so after the first difference the rest of
+// the rounds tell us how we'd answer the same situation, not how the game would have gone.
+pub fn replay(path: &str, team_id: i16, options: &Options) -> SerenityResult<ReplayResult> {
+    let log = try!(GameLog::load(path));
+    let start = match log.start_message(team_id) {
+        Some(start) => start,
+        None => return Err(SerenityError::NoSuchTeam(team_id)),
+    };
+
+    let mut ai = Ai::new(&start, Logger::new(&options.log_dir, options.verbosity), options.seed.unwrap_or(0));
+    // The names were checked when the options were read
+    ai.set_strategies(Composer::from_names(&options.strategies).unwrap());
+    let mut result = ReplayResult { rounds: 0, actions: 0, mismatches: Vec::new() };
+
+    for index in 0..log.rounds() {
+        let events = log.events_message(index, team_id).unwrap();
+        if events.you.bots.iter().all(|bot| !bot.alive) {
+            break;
+        }
+        let round_id = events.round_id;
+        let ours = ai.handle_message(events).actions;
+        let sent = match log.sent_actions(index, team_id) {
+            Some(sent) => sent,
+            None => break,
+        };
+        result.rounds += 1;
+
+        let mut bot_ids: Vec<i16> = ours.iter().chain(sent.iter()).map(|action| action.bot_id).collect();
+        bot_ids.sort();
+        bot_ids.dedup();
+        for bot_id in bot_ids {
+            let ours = ours.iter().find(|action| action.bot_id == bot_id).cloned();
+            let sent = sent.iter().find(|action| action.bot_id == bot_id).cloned();
+            result.actions += 1;
+            let matches = match (&ours, &sent) {
+                (&Some(ref a), &Some(ref b)) => same_action(a, b),
+                _ => false,
+            };
+            if !matches {
+                result.mismatches.push(Mismatch { round_id: round_id, bot_id: bot_id, sent: sent, ours: ours });
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+static TEST_LOG: &'static str = r#"[
+    {"type": "start", "config": {"bots": 1, "fieldRadius": 14, "move": 2, "startHp": 10, "cannon": 1,
+        "radar": 3, "see": 2, "maxCount": 200, "asteroids": 1, "loopTime": 300, "noWait": false},
+     "teams": [
+        {"name": "A", "teamId": 0, "bots": [{"botId": 0, "name": "A 1", "teamId": 0, "alive": true, "pos": {"x": 0, "y": 0}, "hp": 10}]},
+        {"name": "B", "teamId": 1, "bots": [{"botId": 1, "name": "B 1", "teamId": 1, "alive": true, "pos": {"x": 5, "y": 0}, "hp": 10}]}]},
+    {"type": "round", "roundId": 0, "asteroids": [{"x": 1, "y": 1}], "actions": [],
+     "teams": [
+        {"name": "A", "teamId": 0, "bots": [{"botId": 0, "name": "A 1", "teamId": 0, "alive": true, "pos": {"x": 0, "y": 0}, "hp": 10}]},
+        {"name": "B", "teamId": 1, "bots": [{"botId": 1, "name": "B 1", "teamId": 1, "alive": true, "pos": {"x": 5, "y": 0}, "hp": 10}]}],
+     "events": [{"event": "seeAsteroid", "pos": {"x": 1, "y": 1}}]},
+    {"type": "round", "roundId": 1, "asteroids": [{"x": 1, "y": 1}],
+     "actions": [{"botId": 0, "type": "radar", "pos": {"x": 4, "y": 0}}, {"botId": 1, "type": "move", "pos": {"x": 6, "y": 0}}],
+     "teams": [
+        {"name": "A", "teamId": 0, "bots": [{"botId": 0, "name": "A 1", "teamId": 0, "alive": true, "pos": {"x": 0, "y": 0}, "hp": 10}]},
+        {"name": "B", "teamId": 1, "bots": [{"botId": 1, "name": "B 1", "teamId": 1, "alive": true, "pos": {"x": 6, "y": 0}, "hp": 10}]}],
+     "events": [{"event": "move", "botId": 1, "pos": {"x": 6, "y": 0}},
+                {"event": "radarEcho", "pos": {"x": 6, "y": 0}},
+                {"event": "detected", "botId": 1}]},
+    {"type": "endSummary", "winnerTeamId": -1}
+]"#;
+
+#[cfg(test)]
+use defs::EventKind;
+
+#[test]
+fn replay_splits_events_by_team() {
+    let log = GameLog::parse(TEST_LOG).unwrap();
+    assert_eq!(log.rounds(), 2);
+    assert_eq!(log.start_message(0).unwrap().other_teams[0].bots[0].pos, None);
+
+    let kinds = |index: usize, team_id: i16| -> Vec<EventKind> {
+        log.events_message(index, team_id).unwrap().parse_events().0.iter().map(|ev| ev.kind()).collect()
+    };
+    assert_eq!(kinds(0, 0), vec![EventKind::SeeAsteroid]);
+    assert!(kinds(0, 1).is_empty());
+    assert_eq!(kinds(1, 0), vec![EventKind::Echo]);
+    assert_eq!(kinds(1, 1), vec![EventKind::Move, EventKind::Detected]);
+
+    let sent = log.sent_actions(0, 0).unwrap();
+    assert_eq!(sent.len(), 1);
+    assert_eq!(sent[0].kind, ActionKind::Radar);
+    assert!(log.sent_actions(1, 0).is_none());
+}
@@ -0,0 +1,250 @@
+use std::cmp;
+use std::thread;
+use std::time::Duration;
+use automaton::{Automaton, Step};
+use error::{SerenityError, SerenityResult};
+use game::{self, Game, GameResult, Outcome};
+use options::Options;
+use record::{Recorder, Recording};
+use strings::END;
+use transport::{Transport, Websocket};
+use util::{self, Connection};
+
+// Wait before the first reconnect, doubled after each failed one up to the max. Milliseconds.
+const RECONNECT_DELAY: u64 = 250;
+const MAX_RECONNECT_DELAY: u64 = 8000;
+
+// Totals over every game of a session
+#[derive(Debug, Clone, Default)]
+pub struct Stats {
+    pub games: u32,
+    pub wins: u32,
+    pub losses: u32,
+    pub ties: u32,
+    pub rounds: u32,
+    pub bots_alive: u32,
+    // Games we couldn't finish, e.g. because the server went away
+    pub failures: u32,
+}
+
+impl Stats {
+    pub fn new() -> Stats {
+        Stats::default()
+    }
+
+    pub fn record(&mut self, result: &GameResult) {
+        self.games += 1;
+        match result.outcome {
+            Outcome::Won => self.wins += 1,
+            Outcome::Lost => self.losses += 1,
+            Outcome::Tie => self.ties += 1,
+        }
+        self.rounds += result.rounds as u32;
+        self.bots_alive += result.bots_alive as u32;
+    }
+
+    // Whether a session of `games` games is over, 0 for one that never is. Failed games count
+    // too, or a server that's down would keep us trying forever.
+    pub fn is_done(&self, games: u32) -> bool {
+        games != 0 && self.games + self.failures >= games
+    }
+
+    pub fn render(&self) -> String {
+        if self.games == 0 {
+            return format!("No games played, {} failed", self.failures);
+        }
+        let games = self.games as f32;
+        format!("{} games: {} won, {} lost, {} tied ({:.0}% won), {:.1} rounds and {:.1} bots left per game, {} failed",
+                self.games, self.wins, self.losses, self.ties,
+                100.0 * self.wins as f32 / games,
+                self.rounds as f32 / games,
+                self.bots_alive as f32 / games,
+                self.failures)
+    }
+}
+
+// Plays options.games games, or forever if it's 0, connecting again after each one since the
+// server closes the connection when a game ends. With a single game any error is returned, in
+// a longer session we log it and try again after the requeue delay. A failed game counts
+// towards options.games.
+pub fn run(options: &Options) -> SerenityResult<Stats> {
+    let mut stats = Stats::new();
+
+    loop {
+        match play(options) {
+            Ok(result) => stats.record(&result),
+            Err(e) => {
+                if options.games == 1 {
+                    return Err(e);
+                }
+                println!("Game failed: {}", e);
+                stats.failures += 1;
+            }
+        }
+        println!("Session: {}", stats.render());
+
+        if stats.is_done(options.games) {
+            return Ok(stats);
+        }
+        thread::sleep(Duration::from_millis(options.requeue_delay));
+    }
+}
+
+// Plays one game with a fresh Ai. Malformed or out of order messages are skipped. If the
+// connection breaks we connect and join again, and only give up after options.reconnects tries.
+pub fn play(options: &Options) -> SerenityResult<GameResult> {
+    let mut recorder = match options.record {
+        Some(ref path) => Some(try!(Recorder::create(path))),
+        None => None,
+    };
+    let mut connection = try!(util::connect(&options.host, options.port));
+    let mut automaton = Automaton::new(Game::new(options));
+    automaton.connect();
+
+    loop {
+        let mut transport = Websocket::new(connection);
+        let played = match recorder {
+            Some(ref mut recorder) => {
+                try!(recorder.connected());
+                play_transport(&mut automaton, &mut Recording::new(transport, recorder))
+            }
+            None => play_transport(&mut automaton, &mut transport),
+        };
+        let lost = match played {
+            Ok(()) => SerenityError::UnexpectedMessage { expected: END, got: "end of connection".to_string() },
+            // Joining again won't change the server's mind, e.g. the game we were in has ended
+            // or the server hasn't noticed yet that our old connection is gone
+            Err(SerenityError::Refused(reason)) => {
+                if automaton.is_resuming() {
+                    println!("Could not resume the game.");
+                }
+                return Err(SerenityError::Refused(reason));
+            }
+            Err(e) => e,
+        };
+        if automaton.is_finished() {
+            break;
+        }
+
+        println!("Lost the connection: {}", lost);
+        connection = try!(reconnect(options, lost));
+        automaton.reconnect();
+    }
+
+    match automaton.hooks().result() {
+        Some(result) => Ok(result.clone()),
+        None => Err(SerenityError::UnexpectedMessage { expected: END, got: "end of connection".to_string() }),
+    }
+}
+
+// Runs the automaton until the game ends or the connection does
+pub fn play_transport<T: Transport>(automaton: &mut Automaton<Game>, transport: &mut T) -> SerenityResult<()> {
+    loop {
+        let text = match transport.receive() {
+            Ok(Some(text)) => text,
+            Ok(None) => return Ok(()),
+            Err(e) => {
+                transport.close();
+                return Err(e);
+            }
+        };
+
+        let incoming = match game::parse_incoming(&text) {
+            Ok(incoming) => incoming,
+            // Nothing more is coming our way
+            Err(SerenityError::Refused(reason)) => {
+                transport.close();
+                return Err(SerenityError::Refused(reason));
+            }
+            Err(e) => {
+                println!("{}, ignoring.", e);
+                continue;
+            }
+        };
+
+        match automaton.handle(incoming) {
+            Step::Reply(reply) => try!(transport.send(&reply)),
+            Step::Nothing => (),
+            Step::Ignored(reason) => println!("{}, ignoring.", reason),
+            Step::Finished => return Ok(()),
+        }
+    }
+}
+
+// Connects again with exponential backoff. Returns the last error if we run out of tries,
+// `lost` if we weren't allowed any.
+fn reconnect(options: &Options, lost: SerenityError) -> SerenityResult<Connection> {
+    let mut error = lost;
+    let mut delay = RECONNECT_DELAY;
+
+    for attempt in 1..options.reconnects + 1 {
+        thread::sleep(Duration::from_millis(delay));
+        println!("Reconnecting, try {} of {}.", attempt, options.reconnects);
+        match util::connect(&options.host, options.port) {
+            Ok(connection) => return Ok(connection),
+            Err(e) => error = e,
+        }
+        delay = cmp::min(delay * 2, MAX_RECONNECT_DELAY);
+    }
+    return Err(error);
+}
+
+#[test]
+fn stats_add_up_games() {
+    let mut stats = Stats::new();
+    stats.record(&GameResult { outcome: Outcome::Won, rounds: 40, bots_alive: 2 });
+    stats.record(&GameResult { outcome: Outcome::Tie, rounds: 200, bots_alive: 1 });
+    assert_eq!(stats.games, 2);
+    assert_eq!(stats.wins, 1);
+    assert_eq!(stats.ties, 1);
+    assert_eq!(stats.rounds, 240);
+    assert_eq!(stats.render(), "2 games: 1 won, 0 lost, 1 tied (50% won), 120.0 rounds and 1.5 bots left per game, 0 failed");
+
+    // Games that failed end the session too
+    assert!(!stats.is_done(3) && !stats.is_done(0));
+    stats.failures += 1;
+    assert!(stats.is_done(3) && !stats.is_done(0));
+}
+
+// Hands out the messages it was made with, and keeps what we send
+#[cfg(test)]
+struct Script {
+    incoming: Vec<String>,
+    sent: Vec<String>,
+}
+
+#[cfg(test)]
+impl Transport for Script {
+    fn receive(&mut self) -> SerenityResult<Option<String>> {
+        if self.incoming.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(self.incoming.remove(0)))
+    }
+
+    fn send(&mut self, text: &str) -> SerenityResult<()> {
+        self.sent.push(text.to_string());
+        Ok(())
+    }
+}
+
+#[test]
+fn rejoining_a_started_game_fails() {
+    let options = Options { verbosity: 0, ..Options::defaults() };
+    let mut automaton = Automaton::new(Game::new(&options));
+    automaton.connect();
+    automaton.reconnect();
+
+    // What the server says to a join it can't give a team in the running game, and then nothing more
+    let connected = r#"{"type": "connected", "teamId": 0, "config": {"bots": 3, "fieldRadius": 14, "move": 2,
+        "startHp": 10, "cannon": 1, "radar": 3, "see": 2, "maxCount": 200, "asteroids": 0, "loopTime": 300, "noWait": false}}"#;
+    let mut script = Script {
+        incoming: vec![connected.to_string(), r#"{"type": "error", "data": "Already started"}"#.to_string()],
+        sent: Vec::new(),
+    };
+    match play_transport(&mut automaton, &mut script) {
+        Err(SerenityError::Refused(reason)) => assert_eq!(reason, "Already started"),
+        other => panic!("Expected a refusal, got {:?}", other),
+    }
+    assert_eq!(script.sent.len(), 1);
+}
@@ -0,0 +1,626 @@
+// An offline version of the game server. The rules here are ported from
+// server/server/rules/*.js and are applied in the same order as in game.js, so the
+// events a team gets from a round should be exactly the ones the Node server would send.
+// That lets us play whole matches against our Ai inside `cargo test`.
+
+use rand::{Rng, SeedableRng, StdRng};
+use defs::{Action, ActionKind, Bot, Config, Event, IncomingEnd, IncomingEvents, MaybeEvent, Start, Team, TIE_TEAM_ID};
+use defs::{DamagedEvent, DetectedEvent, DieEvent, EchoEvent, HitEvent, MoveEvent, NoactionEvent, SeeAsteroidEvent, SeeEvent};
+use position::Pos;
+use strings::{END, EVENTS, START};
+use ai::Ai;
+use log::Logger;
+
+#[derive(Debug, Clone)]
+struct SimBot {
+    bot_id: i16,
+    name: String,
+    team_id: i16,
+    hp: i16,
+    pos: Pos,
+}
+
+impl SimBot {
+    fn is_alive(&self) -> bool {
+        self.hp > 0 // exactly 0 hp is dead.
+    }
+}
+
+#[derive(Debug, Clone)]
+struct SimTeam {
+    team_id: i16,
+    name: String,
+}
+
+// Who a message is addressed to, like `target` in rules/tools.js
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum Target {
+    Team(i16),
+    All,
+}
+
+pub struct Simulator {
+    config: Config,
+    teams: Vec<SimTeam>,
+    bots: Vec<SimBot>,
+    asteroids: Vec<Pos>,
+    round_id: i16,
+    winner_team_id: Option<i16>,
+}
+
+#[allow(dead_code)]
+impl Simulator {
+    // Places bots and asteroids on shuffled positions like `initialise` in game.js does.
+    // The same seed always gives the same layout.
+    pub fn new(config: Config, team_names: &Vec<String>, seed: usize) -> Simulator {
+        let mut rng: StdRng = SeedableRng::from_seed(&[seed][..]);
+        let mut free_positions = field_positions(config.field_radius);
+        rng.shuffle(&mut free_positions[..]);
+
+        let mut layout: Vec<(String, Vec<Pos>)> = Vec::new();
+        for name in team_names {
+            let positions = (0..config.bots)
+                .map(|_| free_positions.pop().expect("Field too small for all bots"))
+                .collect();
+            layout.push((name.clone(), positions));
+        }
+        let asteroids = (0..config.asteroids)
+            .filter_map(|_| free_positions.pop())
+            .collect();
+
+        Simulator::with_layout(config, layout, asteroids)
+    }
+
+    // Sets up a game with known positions, one (team name, bot positions) tuple per team.
+    // Team and bot ids are handed out in order, starting from 0.
+    pub fn with_layout(config: Config, layout: Vec<(String, Vec<Pos>)>, asteroids: Vec<Pos>) -> Simulator {
+        let mut teams: Vec<SimTeam> = Vec::new();
+        let mut bots: Vec<SimBot> = Vec::new();
+
+        for (team_id, (name, positions)) in layout.into_iter().enumerate() {
+            for (index, pos) in positions.into_iter().enumerate() {
+                let bot_id = bots.len() as i16;
+                bots.push(SimBot {
+                    bot_id: bot_id,
+                    name: format!("{} {}", name, index + 1),
+                    team_id: team_id as i16,
+                    hp: config.start_hp,
+                    pos: pos,
+                });
+            }
+            teams.push(SimTeam { team_id: team_id as i16, name: name });
+        }
+
+        Simulator {
+            config: config,
+            teams: teams,
+            bots: bots,
+            asteroids: asteroids,
+            round_id: 0,
+            winner_team_id: None,
+        }
+    }
+
+    pub fn team_ids(&self) -> Vec<i16> {
+        self.teams.iter().map(|team| team.team_id).collect()
+    }
+
+    pub fn asteroids(&self) -> &Vec<Pos> {
+        &self.asteroids
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.winner_team_id.is_some()
+    }
+
+    // None while the game is running, TIE_TEAM_ID on a tie
+    pub fn winner_team_id(&self) -> Option<i16> {
+        self.winner_team_id
+    }
+
+    pub fn start_message(&self, team_id: i16) -> Start {
+        Start {
+            event_type: START.to_string(),
+            config: self.config,
+            you: self.your_team(team_id),
+            other_teams: self.other_teams(team_id),
+        }
+    }
+
+    pub fn end_message(&self, team_id: i16) -> Option<IncomingEnd> {
+        self.winner_team_id.map(|winner| IncomingEnd {
+            event_type: END.to_string(),
+            you: self.your_team(team_id),
+            winner_team_id: winner,
+        })
+    }
+
+    // Plays one round with the given (team_id, actions) and returns the events message for
+    // every team. Round 0 ignores all actions, like the server does.
+    pub fn play_round(&mut self, team_actions: &Vec<(i16, Vec<Action>)>) -> Vec<(i16, IncomingEvents)> {
+        debug_assert!(!self.is_finished(), "Can't play a round after the game has ended.");
+        let actions = if self.round_id > 0 { self.filter_actions(team_actions) } else { Vec::new() };
+
+        // Only bots alive at the start of the round take part in it. Bots dying this round
+        // stay in here until the round is over, just like world.bots on the server.
+        let mut active: Vec<SimBot> = self.bots.iter().filter(|bot| bot.is_alive()).cloned().collect();
+
+        let mut messages: Vec<(Target, Event)> = Vec::new();
+        messages.append(&mut self.noaction_rule(&actions, &active));
+        messages.append(&mut self.move_rule(&actions, &mut active));
+        messages.append(&mut self.cannon_rule(&actions, &mut active));
+        messages.append(&mut self.dead_rule(&active));
+        messages.append(&mut self.see_rule(&active));
+        messages.append(&mut self.see_asteroid_rule(&active));
+        messages.append(&mut self.radar_rule(&actions, &active));
+        self.winner_team_id = self.end_rule(&active);
+
+        for bot in active {
+            if let Some(world_bot) = self.bots.iter_mut().find(|b| b.bot_id == bot.bot_id) {
+                *world_bot = bot;
+            }
+        }
+
+        let round_id = self.round_id;
+        self.round_id += 1;
+
+        self.team_ids()
+            .into_iter()
+            .map(|team_id| (team_id, self.events_message(team_id, round_id, &messages)))
+            .collect()
+    }
+
+    // Only one action per bot (the first one), and only for own live bots
+    fn filter_actions(&self, team_actions: &Vec<(i16, Vec<Action>)>) -> Vec<Action> {
+        let mut result: Vec<Action> = Vec::new();
+        for &(team_id, ref actions) in team_actions {
+            let mut acted: Vec<i16> = Vec::new();
+            for action in actions {
+                if acted.contains(&action.bot_id) {
+                    continue;
+                }
+                acted.push(action.bot_id);
+                let is_own_live_bot = self.bots.iter()
+                    .find(|bot| bot.bot_id == action.bot_id && bot.team_id == team_id && bot.is_alive())
+                    .is_some();
+                if is_own_live_bot {
+                    result.push(action.clone());
+                }
+            }
+        }
+        result
+    }
+
+    fn noaction_rule(&self, actions: &Vec<Action>, active: &Vec<SimBot>) -> Vec<(Target, Event)> {
+        active.iter()
+            .filter(|bot| actions.iter().find(|ac| ac.bot_id == bot.bot_id).is_none())
+            .map(|bot| (Target::Team(bot.team_id), Event::Noaction(NoactionEvent { bot_id: bot.bot_id })))
+            .collect()
+    }
+
+    fn move_rule(&self, actions: &Vec<Action>, active: &mut Vec<SimBot>) -> Vec<(Target, Event)> {
+        let mut moves: Vec<(usize, Pos)> = Vec::new();
+        for action in actions.iter().filter(|ac| ac.kind == ActionKind::Move) {
+            let index = match active.iter().position(|bot| bot.bot_id == action.bot_id) {
+                Some(index) => index,
+                None => continue,
+            };
+            let bot_pos = active[index].pos;
+            let destination = action.pos.clamp(&self.config.field_radius);
+
+            if self.asteroids.contains(&destination) { continue; }     // Trying to move into asteroid
+            if destination == bot_pos { continue; }                     // Not actually moving
+            if destination.distance(bot_pos) > self.config.moves_allowed { continue; } // Too far
+
+            moves.push((index, destination));
+        }
+
+        let mut messages = Vec::new();
+        for (index, destination) in moves {
+            let bot = &mut active[index];
+            bot.pos = destination;
+            messages.push((Target::Team(bot.team_id), Event::Move(MoveEvent { bot_id: bot.bot_id, pos: destination })));
+        }
+        messages
+    }
+
+    fn cannon_rule(&self, actions: &Vec<Action>, active: &mut Vec<SimBot>) -> Vec<(Target, Event)> {
+        // (source index, target index, damage)
+        let mut blasts: Vec<(usize, usize, i16)> = Vec::new();
+        for action in actions.iter().filter(|ac| ac.kind == ActionKind::Cannon) {
+            // Cannon out-of-bounds, do nothing
+            if action.pos.distance(Pos::origo()) > self.config.field_radius {
+                continue;
+            }
+            let source = match active.iter().position(|bot| bot.bot_id == action.bot_id) {
+                Some(source) => source,
+                None => continue,
+            };
+            for (target, bot) in active.iter().enumerate() {
+                let distance = bot.pos.distance(action.pos);
+                if distance <= self.config.cannon {
+                    blasts.push((source, target, 1 + self.config.cannon - distance));
+                }
+            }
+        }
+
+        for &(_, target, damage) in &blasts {
+            active[target].hp -= damage;
+        }
+
+        let mut messages = Vec::new();
+        for (source, target, damage) in blasts {
+            let (source, target) = (&active[source], &active[target]);
+            messages.push((Target::Team(source.team_id), Event::Hit(HitEvent { bot_id: target.bot_id, source: source.bot_id })));
+            messages.push((Target::Team(target.team_id), Event::Damaged(DamagedEvent { bot_id: target.bot_id, damage: damage })));
+        }
+        messages
+    }
+
+    fn dead_rule(&self, active: &Vec<SimBot>) -> Vec<(Target, Event)> {
+        active.iter()
+            .filter(|bot| !bot.is_alive())
+            .map(|bot| (Target::All, Event::Die(DieEvent { bot_id: bot.bot_id })))
+            .collect()
+    }
+
+    fn see_rule(&self, active: &Vec<SimBot>) -> Vec<(Target, Event)> {
+        let mut messages = Vec::new();
+        for source in active {
+            for target in active {
+                if source.team_id != target.team_id && source.pos.distance(target.pos) <= self.config.see {
+                    messages.push((Target::Team(source.team_id),
+                        Event::See(SeeEvent { source: source.bot_id, bot_id: target.bot_id, pos: target.pos })));
+                }
+            }
+        }
+        messages
+    }
+
+    fn see_asteroid_rule(&self, active: &Vec<SimBot>) -> Vec<(Target, Event)> {
+        let mut messages = Vec::new();
+        for source in active {
+            for asteroid in &self.asteroids {
+                if source.pos.distance(*asteroid) <= self.config.see {
+                    messages.push((Target::Team(source.team_id), Event::SeeAsteroid(SeeAsteroidEvent { pos: *asteroid })));
+                }
+            }
+        }
+        messages
+    }
+
+    fn radar_rule(&self, actions: &Vec<Action>, active: &Vec<SimBot>) -> Vec<(Target, Event)> {
+        let mut echoes = Vec::new();
+        let mut detections = Vec::new();
+
+        for action in actions.iter().filter(|ac| ac.kind == ActionKind::Radar) {
+            // Radar out-of-bounds, do nothing
+            if action.pos.distance(Pos::origo()) > self.config.field_radius {
+                continue;
+            }
+            let source = match active.iter().find(|bot| bot.bot_id == action.bot_id) {
+                Some(source) => source,
+                None => continue,
+            };
+
+            for bot in active {
+                if bot.pos.distance(action.pos) <= self.config.radar && bot.team_id != source.team_id {
+                    echoes.push((Target::Team(source.team_id), Event::Echo(EchoEvent { pos: bot.pos })));
+                    detections.push((Target::Team(bot.team_id), Event::Detected(DetectedEvent { bot_id: bot.bot_id })));
+                }
+            }
+            for asteroid in &self.asteroids {
+                if asteroid.distance(action.pos) <= self.config.radar {
+                    echoes.push((Target::Team(source.team_id), Event::Echo(EchoEvent { pos: *asteroid })));
+                }
+            }
+        }
+
+        echoes.append(&mut detections);
+        echoes
+    }
+
+    // Returns the winner team id if the game is over after this round
+    fn end_rule(&self, active: &Vec<SimBot>) -> Option<i16> {
+        if self.round_id >= self.config.max_count {
+            if active.is_empty() {
+                // Everyone dead
+                return Some(TIE_TEAM_ID);
+            }
+            // Counting hp per team, the only team with max hp wins
+            let mut team_hps: Vec<(i16, i16)> = Vec::new();
+            for bot in active {
+                match team_hps.iter().position(|&(team_id, _)| team_id == bot.team_id) {
+                    Some(index) => team_hps[index].1 += bot.hp,
+                    None => team_hps.push((bot.team_id, bot.hp)),
+                }
+            }
+            let max_hp = team_hps.iter().map(|&(_, hp)| hp).max().unwrap();
+            let teams_with_max_hp: Vec<i16> = team_hps.iter()
+                .filter(|&&(_, hp)| hp == max_hp)
+                .map(|&(team_id, _)| team_id)
+                .collect();
+            if teams_with_max_hp.len() == 1 {
+                return Some(teams_with_max_hp[0]);
+            } else {
+                return Some(TIE_TEAM_ID);
+            }
+        }
+
+        // Note that bots that died this round still count here, as they do on the server
+        let mut team_ids: Vec<i16> = active.iter().map(|bot| bot.team_id).collect();
+        team_ids.sort();
+        team_ids.dedup();
+        if team_ids.len() == 1 {
+            return Some(team_ids[0]);
+        }
+        None
+    }
+
+    fn events_message(&self, team_id: i16, round_id: i16, messages: &Vec<(Target, Event)>) -> IncomingEvents {
+        let mut events: Vec<Event> = messages.iter()
+            .filter(|&&(target, _)| target == Target::Team(team_id))
+            .map(|&(_, ref ev)| ev.clone())
+            .collect();
+        events.extend(messages.iter()
+            .filter(|&&(target, _)| target == Target::All)
+            .map(|&(_, ref ev)| ev.clone()));
+
+        IncomingEvents {
+            event_type: EVENTS.to_string(),
+            round_id: round_id,
+            config: self.config,
+            you: self.your_team(team_id),
+            other_teams: self.other_teams(team_id),
+            events: events.into_iter().map(MaybeEvent::from).collect(),
+        }
+    }
+
+    fn your_team(&self, team_id: i16) -> Team {
+        let team = self.teams.iter().find(|team| team.team_id == team_id).expect("No team with this id");
+        Team {
+            name: team.name.clone(),
+            team_id: team_id,
+            bots: self.bots.iter()
+                .filter(|bot| bot.team_id == team_id)
+                .map(|bot| bot_info(bot, true))
+                .collect(),
+        }
+    }
+
+    // Mirrors combineOtherTeams in messages.js, which lists every bot not on your team
+    // under each of the other teams.
+    fn other_teams(&self, team_id: i16) -> Vec<Team> {
+        self.teams.iter()
+            .filter(|team| team.team_id != team_id)
+            .map(|team| Team {
+                name: team.name.clone(),
+                team_id: team.team_id,
+                bots: self.bots.iter()
+                    .filter(|bot| bot.team_id != team_id)
+                    .map(|bot| bot_info(bot, false))
+                    .collect(),
+            })
+            .collect()
+    }
+}
+
+fn bot_info(bot: &SimBot, include_pos_and_hp: bool) -> Bot {
+    Bot {
+        bot_id: bot.bot_id,
+        name: bot.name.clone(),
+        team_id: bot.team_id,
+        alive: bot.is_alive(),
+        pos: if include_pos_and_hp { Some(bot.pos) } else { None },
+        hp: if include_pos_and_hp { Some(bot.hp) } else { None },
+    }
+}
+
+// Every position on the field except origo, same as position.neighbours(origo, fieldRadius)
+fn field_positions(field_radius: i16) -> Vec<Pos> {
+    let mut result = Vec::new();
+    for x in -field_radius..field_radius + 1 {
+        for y in -field_radius..field_radius + 1 {
+            let pos = Pos { x: x, y: y };
+            if pos != Pos::origo() && pos.distance(Pos::origo()) <= field_radius {
+                result.push(pos);
+            }
+        }
+    }
+    result
+}
+
+// The values in server/default-config.json
+#[allow(dead_code)]
+pub fn default_config() -> Config {
+    Config {
+        bots: 3,
+        field_radius: 14,
+        moves_allowed: 2,
+        start_hp: 10,
+        cannon: 1,
+        radar: 3,
+        see: 2,
+        max_count: 200,
+        asteroids: 0,
+        loop_time: 300,
+        no_wait: false,
+    }
+}
+
+// Anything that can play a team in a simulated match, e.g. our Ai or a scripted opponent
+pub trait Player {
+    // Answers the events of a round with the team's actions
+    fn play_round(&mut self, events: IncomingEvents) -> Vec<Action>;
+}
+
+impl Player for Ai {
+    fn play_round(&mut self, events: IncomingEvents) -> Vec<Action> {
+        self.handle_message(events).actions
+    }
+}
+
+impl<P: Player + ?Sized> Player for Box<P> {
+    fn play_round(&mut self, events: IncomingEvents) -> Vec<Action> {
+        (**self).play_round(events)
+    }
+}
+
+// Plays a whole match between one Ai per team, seeded with seed, and returns the end
+// message each team got
+#[allow(dead_code)]
+pub fn run_match(sim: Simulator, seed: usize) -> Vec<(i16, IncomingEnd)> {
+    play_match(sim, seed).0
+}
+
+// Like run_match, but also gives back the Ai of each team to look at after the game
+pub fn play_match(sim: Simulator, seed: usize) -> (Vec<(i16, IncomingEnd)>, Vec<(i16, Ai)>) {
+    let mut ais: Vec<(i16, Ai)> = sim.team_ids()
+        .into_iter()
+        .map(|team_id| (team_id, Ai::new(&sim.start_message(team_id), Logger::disabled(), seed)))
+        .collect();
+    let ends = play_players(sim, &mut ais);
+    (ends, ais)
+}
+
+// Plays a whole match with a player for each team and returns the end message each team got
+pub fn play_players<P: Player>(mut sim: Simulator, players: &mut Vec<(i16, P)>) -> Vec<(i16, IncomingEnd)> {
+    let mut team_actions: Vec<(i16, Vec<Action>)> = Vec::new();
+
+    while !sim.is_finished() {
+        let events = sim.play_round(&team_actions);
+        team_actions = events.into_iter()
+            .map(|(team_id, events_msg)| {
+                let player = &mut players.iter_mut().find(|entry| entry.0 == team_id).unwrap().1;
+                (team_id, player.play_round(events_msg))
+            })
+            .collect();
+    }
+
+    sim.team_ids()
+        .into_iter()
+        .map(|team_id| (team_id, sim.end_message(team_id).unwrap()))
+        .collect()
+}
+
+#[cfg(test)]
+fn action(bot_id: i16, kind: ActionKind, x: i16, y: i16) -> Action {
+    Action { bot_id: bot_id, kind: kind, pos: Pos::new(x, y) }
+}
+
+#[cfg(test)]
+fn two_bot_sim(asteroids: Vec<Pos>) -> Simulator {
+    let mut config = default_config();
+    config.bots = 1;
+    Simulator::with_layout(config, vec![
+        ("A".to_string(), vec![Pos::new(0, 0)]),
+        ("B".to_string(), vec![Pos::new(5, 0)]),
+    ], asteroids)
+}
+
+#[cfg(test)]
+use defs::EventKind;
+
+#[cfg(test)]
+fn event_kinds(events: &IncomingEvents) -> Vec<EventKind> {
+    events.parse_events().0.iter().map(|ev| ev.kind()).collect()
+}
+
+#[test]
+fn round_zero_is_all_noactions() {
+    let mut sim = two_bot_sim(vec![]);
+    let events = sim.play_round(&vec![(0, vec![action(0, ActionKind::Cannon, 5, 0)])]);
+    assert_eq!(event_kinds(&events[0].1), vec![EventKind::Noaction]);
+    assert_eq!(events[1].1.you.bots[0].hp, Some(10));
+}
+
+#[test]
+fn cannon_damage_falls_off_with_distance() {
+    let mut sim = two_bot_sim(vec![]);
+    sim.play_round(&vec![]);
+    let events = sim.play_round(&vec![(0, vec![action(0, ActionKind::Cannon, 4, 0)]), (1, vec![action(1, ActionKind::Cannon, 5, 0)])]);
+
+    // Bot 1 got 1 damage from bot 0, and shot itself for 2
+    assert_eq!(events[1].1.you.bots[0].hp, Some(7));
+    let damages: Vec<i16> = events[1].1.parse_events().0.iter()
+        .filter_map(|ev| match *ev { Event::Damaged(ref damaged) => Some(damaged.damage), _ => None })
+        .collect();
+    assert_eq!(damages, vec![1, 2]);
+}
+
+#[test]
+fn moves_are_clamped_and_blocked_by_asteroids() {
+    let mut sim = two_bot_sim(vec![Pos::new(0, 1)]);
+    sim.play_round(&vec![]);
+    let events = sim.play_round(&vec![(0, vec![action(0, ActionKind::Move, 0, 1)]), (1, vec![action(1, ActionKind::Move, 30, 0)])]);
+    assert_eq!(events[0].1.you.bots[0].pos, Some(Pos::new(0, 0)));
+    // (30, 0) clamps to (14, 0), which is too far to move to
+    assert_eq!(events[1].1.you.bots[0].pos, Some(Pos::new(5, 0)));
+
+    let events = sim.play_round(&vec![(1, vec![action(1, ActionKind::Move, 7, 0)])]);
+    let moved = *events[1].1.parse_events().0.iter().find(|ev| ev.kind() == EventKind::Move).unwrap();
+    assert_eq!(moved.pos(), Some(Pos::new(7, 0)));
+}
+
+#[test]
+fn radar_echoes_bots_and_asteroids_and_detects() {
+    let mut sim = two_bot_sim(vec![Pos::new(4, -1)]);
+    sim.play_round(&vec![]);
+    let events = sim.play_round(&vec![(0, vec![action(0, ActionKind::Radar, 5, 0)])]);
+    let echoes: Vec<Pos> = events[0].1.parse_events().0.iter()
+        .filter(|ev| ev.kind() == EventKind::Echo)
+        .map(|ev| ev.pos().unwrap())
+        .collect();
+    assert_eq!(echoes, vec![Pos::new(5, 0), Pos::new(4, -1)]);
+    assert!(event_kinds(&events[1].1).contains(&EventKind::Detected));
+    // Bot 1 is within see range of the asteroid
+    assert!(event_kinds(&events[1].1).contains(&EventKind::SeeAsteroid));
+}
+
+#[test]
+fn last_team_standing_wins() {
+    let mut sim = two_bot_sim(vec![]);
+    sim.play_round(&vec![]);
+    for _ in 0..4 {
+        assert!(!sim.is_finished());
+        sim.play_round(&vec![(0, vec![action(0, ActionKind::Cannon, 5, 0)])]);
+    }
+    let events = sim.play_round(&vec![(0, vec![action(0, ActionKind::Cannon, 5, 0)])]);
+    assert!(event_kinds(&events[0].1).contains(&EventKind::Die));
+    // The server only drops dead bots from the world in the next round
+    assert!(!sim.is_finished());
+    sim.play_round(&vec![]);
+    assert_eq!(sim.winner_team_id(), Some(0));
+    assert_eq!(sim.end_message(1).unwrap().you.bots[0].alive, false);
+}
+
+#[test]
+fn max_count_ends_on_hp() {
+    let mut sim = two_bot_sim(vec![]);
+    sim.config.max_count = 2;
+    sim.play_round(&vec![]);
+    sim.play_round(&vec![(0, vec![action(0, ActionKind::Cannon, 5, 0)])]);
+    assert!(!sim.is_finished());
+    sim.play_round(&vec![]);
+    assert_eq!(sim.winner_team_id(), Some(0));
+}
+
+#[test]
+fn whole_match_runs_offline() {
+    let names = vec!["Serenity".to_string(), "Firefly".to_string()];
+    let (ends, ais) = play_match(Simulator::new(default_config(), &names, 1), 1);
+    assert_eq!(ends.len(), 2);
+    assert_eq!(ends[0].1.winner_team_id, ends[1].1.winner_team_id);
+    for &(team_id, ref ai) in &ais {
+        let reports: Vec<String> = ai.violations().iter().map(|&(round_id, ref report)| format!("{}: {}", round_id, report)).collect();
+        assert!(reports.is_empty(), "Team {} sent invalid actions: {:?}", team_id, reports);
+    }
+}
+
+#[test]
+fn same_seed_plays_the_same_match() {
+    let names = vec!["Serenity".to_string(), "Firefly".to_string()];
+    let first = run_match(Simulator::new(default_config(), &names, 7), 7);
+    let second = run_match(Simulator::new(default_config(), &names, 7), 7);
+    assert_eq!(format!("{:?}", first), format!("{:?}", second));
+}
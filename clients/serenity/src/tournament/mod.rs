@@ -0,0 +1,152 @@
+use std::cmp;
+use ai::Ai;
+use ai::strategy::Composer;
+use defs::{IncomingEnd, Start, TIE_TEAM_ID};
+use log::Logger;
+use options::Options;
+use sim::{self, Player, Simulator};
+
+pub mod scripted;
+
+use self::scripted::{Idle, Random, Sniper};
+
+// Players that can take part. serenity plays with --strategies, serenity:<list> with the
+// comma separated strategies in list, so two sets of strategies can be pitted against each other.
+pub const PLAYERS: &'static str = "serenity, serenity:<strategies>, random, sniper, idle";
+
+const SERENITY: &'static str = "serenity";
+
+// How one player did over the tournament
+#[derive(Debug, Clone)]
+pub struct Standing {
+    pub name: String,
+    pub games: u32,
+    pub wins: u32,
+    pub ties: u32,
+    pub losses: u32,
+    // Summed over the games, of the bots still alive at the end
+    pub hp_left: u32,
+}
+
+impl Standing {
+    fn new(name: &str) -> Standing {
+        Standing { name: name.to_string(), games: 0, wins: 0, ties: 0, losses: 0, hp_left: 0 }
+    }
+
+    fn record(&mut self, end: &IncomingEnd) {
+        self.games += 1;
+        if end.winner_team_id == end.you.team_id {
+            self.wins += 1;
+        } else if end.winner_team_id == TIE_TEAM_ID {
+            self.ties += 1;
+        } else {
+            self.losses += 1;
+        }
+        self.hp_left += end.you.bots.iter()
+            .filter(|bot| bot.alive)
+            .map(|bot| cmp::max(bot.hp.unwrap_or(0), 0) as u32)
+            .sum::<u32>();
+    }
+}
+
+// Makes the player with the name for a team, None if there's no such player
+fn new_player(name: &str, start: &Start, seed: usize, options: &Options) -> Option<Box<Player>> {
+    let player: Box<Player> = match name {
+        "random" => Box::new(Random::new(start, seed)),
+        "sniper" => Box::new(Sniper::new(start, seed)),
+        "idle" => Box::new(Idle),
+        _ => {
+            let strategies = if name == SERENITY {
+                options.strategies.clone()
+            } else if name.starts_with("serenity:") {
+                name[SERENITY.len() + 1..].to_string()
+            } else {
+                return None;
+            };
+            let mut ai = Ai::new(start, Logger::disabled(), seed);
+            match Composer::from_names(&strategies) {
+                Ok(composer) => ai.set_strategies(composer),
+                Err(_) => return None,
+            }
+            Box::new(ai)
+        }
+    };
+    Some(player)
+}
+
+// Returns the first name that isn't a player we know
+pub fn check_players(names: &Vec<String>, options: &Options) -> Result<(), String> {
+    let sim = Simulator::new(sim::default_config(), names, 0);
+    for (team_id, name) in sim.team_ids().into_iter().zip(names.iter()) {
+        if new_player(name, &sim.start_message(team_id), 0, options).is_none() {
+            return Err(name.clone());
+        }
+    }
+    Ok(())
+}
+
+// Plays the given number of matches between the players on the offline simulator, every
+// player in every match. Match i is played with seed options.seed + i, and the players take
+// turns being the first team so none of them always gets the same spots. The names must have
+// passed check_players.
+pub fn run(names: &Vec<String>, matches: u32, options: &Options) -> Vec<Standing> {
+    let mut standings: Vec<Standing> = names.iter().map(|name| Standing::new(name)).collect();
+    let first_seed = options.seed.unwrap_or(0);
+
+    for index in 0..matches as usize {
+        let seed = first_seed + index;
+        // order[team_id] is the index of the player in names
+        let order: Vec<usize> = (0..names.len()).map(|team| (team + index) % names.len()).collect();
+        let team_names: Vec<String> = order.iter().map(|&player| names[player].clone()).collect();
+
+        let sim = Simulator::new(sim::default_config(), &team_names, seed);
+        let mut players: Vec<(i16, Box<Player>)> = sim.team_ids()
+            .into_iter()
+            .map(|team_id| {
+                let name = &team_names[team_id as usize];
+                (team_id, new_player(name, &sim.start_message(team_id), seed, options).unwrap())
+            })
+            .collect();
+
+        let ends = sim::play_players(sim, &mut players);
+        let winner = ends.first().map_or(TIE_TEAM_ID, |&(_, ref end)| end.winner_team_id);
+        for (team_id, end) in ends {
+            standings[order[team_id as usize]].record(&end);
+        }
+        println!("Match {} of {} (seed {}): {}", index + 1, matches, seed,
+                 if winner == TIE_TEAM_ID { "tie".to_string() } else { format!("{} won", team_names[winner as usize]) });
+    }
+
+    standings
+}
+
+pub fn render(standings: &Vec<Standing>) -> String {
+    let mut table = format!("{:<24} {:>6} {:>6} {:>6} {:>6} {:>7} {:>8}\n",
+                            "Player", "Games", "Won", "Tied", "Lost", "Won %", "HP left");
+    for standing in standings {
+        let games = cmp::max(standing.games, 1) as f32;
+        table.push_str(&format!("{:<24} {:>6} {:>6} {:>6} {:>6} {:>7.1} {:>8.1}\n",
+                                standing.name, standing.games, standing.wins, standing.ties, standing.losses,
+                                100.0 * standing.wins as f32 / games,
+                                standing.hp_left as f32 / games));
+    }
+    table
+}
+
+#[test]
+fn tournament_plays_every_match() {
+    let names = vec!["sniper".to_string(), "idle".to_string()];
+    let options = Options::defaults();
+    assert!(check_players(&names, &options).is_ok());
+    assert_eq!(check_players(&vec!["sniper".to_string(), "serenity:telepathy".to_string()], &options),
+               Err("serenity:telepathy".to_string()));
+
+    let standings = run(&names, 4, &options);
+    for standing in &standings {
+        assert_eq!(standing.games, 4);
+        assert_eq!(standing.wins + standing.ties + standing.losses, 4);
+    }
+    // The sitting ducks never shoot back
+    assert_eq!(standings[1].wins, 0);
+    assert!(render(&standings).contains("sniper"));
+}
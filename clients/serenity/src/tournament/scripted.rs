@@ -0,0 +1,82 @@
+use rand::{Rng, SeedableRng, StdRng};
+use defs::{Action, ActionKind, Config, Event, IncomingEvents, Start};
+use position::Pos;
+use sim::Player;
+use tyckiting_protocol::random::RandomAi;
+
+// Simple opponents for the tournament. They play from a seeded generator so a match between
+// them is the same every time it's run with the same seed.
+
+fn action(bot_id: i16, kind: ActionKind, pos: Pos) -> Action {
+    Action { bot_id: bot_id, kind: kind, pos: pos }
+}
+
+fn seeded(seed: usize, team_id: i16) -> StdRng {
+    SeedableRng::from_seed(&[seed, team_id as usize][..])
+}
+
+// Never does anything, to see that we can find and kill a sitting duck
+pub struct Idle;
+
+impl Player for Idle {
+    fn play_round(&mut self, _events: IncomingEvents) -> Vec<Action> {
+        Vec::new()
+    }
+}
+
+// RandomAi of clients/rust, from the protocol crate: every bot fires, moves or radars at random
+pub struct Random {
+    ai: RandomAi,
+}
+
+impl Random {
+    pub fn new(start: &Start, seed: usize) -> Random {
+        Random { ai: RandomAi::new(&[seed, start.you.team_id as usize]) }
+    }
+}
+
+impl Player for Random {
+    fn play_round(&mut self, events: IncomingEvents) -> Vec<Action> {
+        self.ai.respond(&events.config, &events.you)
+    }
+}
+
+// Radars a random spot with one bot while the others wander, and fires everything at
+// whatever it finds
+pub struct Sniper {
+    config: Config,
+    rng: StdRng,
+}
+
+impl Sniper {
+    pub fn new(start: &Start, seed: usize) -> Sniper {
+        Sniper { config: start.config, rng: seeded(seed, start.you.team_id) }
+    }
+}
+
+impl Player for Sniper {
+    fn play_round(&mut self, events: IncomingEvents) -> Vec<Action> {
+        let found: Option<Pos> = events.parse_events().0.iter()
+            .filter_map(|ev| match *ev {
+                Event::Echo(ref echo) => Some(echo.pos),
+                Event::See(ref see) => Some(see.pos),
+                _ => None,
+            })
+            .find(|pos| !events.you.bots.iter().any(|bot| bot.pos == Some(*pos)));
+        let field = Pos::origo().area(self.config.field_radius);
+
+        let mut actions = Vec::new();
+        for (index, bot) in events.you.bots.iter().filter(|bot| bot.alive).enumerate() {
+            let next = match found {
+                Some(target) => action(bot.bot_id, ActionKind::Cannon, target),
+                None if index == 0 => action(bot.bot_id, ActionKind::Radar, *self.rng.choose(&field).unwrap()),
+                None => {
+                    let moves = bot.pos.unwrap().area(self.config.moves_allowed);
+                    action(bot.bot_id, ActionKind::Move, *self.rng.choose(&moves).unwrap())
+                }
+            };
+            actions.push(next);
+        }
+        actions
+    }
+}
@@ -0,0 +1,50 @@
+use std::str::from_utf8;
+use websocket::{Message, Sender, Receiver};
+use websocket::message::Type;
+use error::{SerenityError, SerenityResult};
+use util::Connection;
+
+// Where the messages of a game come from and go to: the server, or a recording of a game
+// with it (see record).
+pub trait Transport {
+    // The next text message, None once the other end has closed
+    fn receive(&mut self) -> SerenityResult<Option<String>>;
+    fn send(&mut self, text: &str) -> SerenityResult<()>;
+    // We're giving up on the connection
+    fn close(&mut self) {}
+}
+
+pub struct Websocket {
+    connection: Connection,
+}
+
+impl Websocket {
+    pub fn new(connection: Connection) -> Websocket {
+        Websocket { connection: connection }
+    }
+}
+
+impl Transport for Websocket {
+    fn receive(&mut self) -> SerenityResult<Option<String>> {
+        loop {
+            let message: Message = try!(self.connection.1.recv_message());
+            match message.opcode {
+                Type::Text => match from_utf8(&message.payload) {
+                    Ok(text) => return Ok(Some(text.to_string())),
+                    Err(e) => println!("{}, ignoring.", SerenityError::from(e)),
+                },
+                Type::Close => return Ok(None),
+                _ => println!("{}, ignoring.", SerenityError::NotText),
+            }
+        }
+    }
+
+    fn send(&mut self, text: &str) -> SerenityResult<()> {
+        try!(self.connection.0.send_message(&Message::text(text)));
+        return Ok(());
+    }
+
+    fn close(&mut self) {
+        let _ = self.connection.0.send_message(&Message::close());
+    }
+}
@@ -6,45 +6,58 @@ use websocket::Client;
 use std::collections::HashSet;
 use std::hash::Hash;
 
-use rand::Rng;
+use rand::{Rng, SeedableRng, StdRng};
 use position::{Pos};
+use error::{SerenityError, SerenityResult};
 
-static ADDR: &'static str = "ws://localhost:3000";
 static AGENT: &'static str = "rust-websocket";
-static GAME_HOST: &'static str = "10.3.200.123";
-static GAME_PORT: &'static str = "3000";
 
 // Feel free to write a better type annotation. It's not easy.
-pub fn connect() -> (websocket::sender::Sender<websocket::stream::WebSocketStream>,
-                 websocket::receiver::Receiver<websocket::stream::WebSocketStream>) {
-    println!("Using location {}", ADDR);
+pub type Connection = (websocket::sender::Sender<websocket::stream::WebSocketStream>,
+                       websocket::receiver::Receiver<websocket::stream::WebSocketStream>);
+
+pub fn connect(host: &str, port: u16) -> SerenityResult<Connection> {
+    let addr = format!("ws://{}:{}", host, port);
+    println!("Using location {}", addr);
     println!("Using agent {}", AGENT);
-    let url = Url::parse(format!("ws://{}:{}", GAME_HOST, GAME_PORT).as_ref()).expect("Could not parse url, wtf");
-    let request = Client::connect(url).expect("Could not connect to server.");
-    let response = request.send().expect("Failed sending a request to server.");
-    match response.validate() {
-        Ok(()) => (),
-        Err(e) => {
-            println!("Failed to get a response, error: {:?}", e);
-        }
-    }
-    return response.begin().split();
+    let url = try!(Url::parse(addr.as_ref())
+        .map_err(|e| SerenityError::BadUrl { url: addr.clone(), reason: e.to_string() }));
+    let request = try!(Client::connect(url));
+    let response = try!(request.send());
+    try!(response.validate());
+    return Ok(response.begin().split());
+}
+
+// Everything random in serenity comes from a generator made here, so the same seed makes
+// the same decisions given the same messages
+pub fn seeded_rng(seed: &[usize]) -> StdRng {
+    SeedableRng::from_seed(seed)
 }
 
 #[allow(dead_code)]
-pub fn get_random_pos(positions: &Vec<Pos>) -> Pos {
-    let pos = rand::thread_rng().choose(&positions).unwrap();
+pub fn get_random_pos<R: Rng>(rng: &mut R, positions: &Vec<Pos>) -> Pos {
+    let pos = rng.choose(&positions).unwrap();
     pos.clone()
     // Pos { x: rng.gen::<i16>(), y: rng.gen::<i16>() }
 }
 
 #[allow(dead_code)]
-pub fn get_rand_range(min: i16, max: i16) -> i16 {
-    rand::thread_rng().gen_range(min, max + 1)
+pub fn get_rand_range<R: Rng>(rng: &mut R, min: i16, max: i16) -> i16 {
+    rng.gen_range(min, max + 1)
 }
 
+// Keeps the first of every value, in order. Going through a HashSet would shuffle them
+// differently on every run.
 #[allow(dead_code)]
-pub fn dedup_nosort<T: Eq + Hash>(vec: &mut Vec<T>) {
-    let set: HashSet<T> = vec.drain(..).collect();
-    vec.extend(set.into_iter());
+pub fn dedup_nosort<T: Eq + Hash + Clone>(vec: &mut Vec<T>) {
+    let mut seen: HashSet<T> = HashSet::new();
+    vec.retain(|value| seen.insert(value.clone()));
+}
+
+#[test]
+fn connect_refuses_a_host_that_makes_no_url() {
+    match connect("no such host", 2000) {
+        Err(SerenityError::BadUrl { url, .. }) => assert_eq!(url, "ws://no such host:2000"),
+        other => panic!("Expected a bad url, got {:?}", other.map(|_| ())),
+    }
 }
@@ -165,6 +165,18 @@ function Game(config, keepAlive, isStepped, ws, gameLogFile, onEndCallback) {
                     }
 
                     if (started) {
+                        // A team that lost its connection mid-game can join again under
+                        // the same name, it gets the events of the next round on this socket
+                        var dropped = _.find(allPlayers, function (p) {
+                            return p.active && p.name === data.teamName && p.socket.readyState !== p.socket.OPEN;
+                        });
+                        if (dropped) {
+                            console.info("Team", dropped.name, "joined again");
+                            allPlayers = _.without(allPlayers, player);
+                            dropped.socket = socket;
+                            player = dropped;
+                            return;
+                        }
                         if (socket) {
                             socket.send(JSON.stringify({type: "error", data: "Already started"}));
                         }